@@ -0,0 +1,46 @@
+// @version 1.0.0
+// @description Puts files into the service's file input, or drops them on the editor
+// @param files - [{name, mimeType, base64}] to attach
+// @param fileInputSelectors - Candidate <input type="file"> selectors, in order
+// @param dropTargetSelectors - Elements to drop onto when no file input exists
+// @param settleMs - Time given to the page to start uploading
+
+const sleep = (ms) => new Promise((resolve) => setTimeout(resolve, ms));
+
+const toFile = (file) => {
+    const binary = atob(file.base64);
+    const bytes = new Uint8Array(binary.length);
+    for (let i = 0; i < binary.length; i++) bytes[i] = binary.charCodeAt(i);
+    return new File([bytes], file.name, { type: file.mimeType });
+};
+
+const transfer = new DataTransfer();
+args.files.forEach((file) => transfer.items.add(toFile(file)));
+
+const first = (selectors) => {
+    for (const selector of selectors) {
+        const el = document.querySelector(selector);
+        if (el) return { el, selector };
+    }
+    return null;
+};
+
+const input = first(args.fileInputSelectors);
+if (input && input.el.tagName === 'INPUT') {
+    input.el.files = transfer.files;
+    input.el.dispatchEvent(new Event('input', { bubbles: true }));
+    input.el.dispatchEvent(new Event('change', { bubbles: true }));
+    await sleep(args.settleMs);
+    return { method: 'file_input', selector: input.selector };
+}
+
+const target = first(args.dropTargetSelectors);
+if (target) {
+    for (const type of ['dragenter', 'dragover', 'drop']) {
+        target.el.dispatchEvent(new DragEvent(type, { bubbles: true, cancelable: true, dataTransfer: transfer }));
+    }
+    await sleep(args.settleMs);
+    return { method: 'drop', selector: target.selector };
+}
+
+throw new Error('No file input or drop target found for attachments');
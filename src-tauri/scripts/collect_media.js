@@ -0,0 +1,59 @@
+// @version 1.0.0
+// @description Fetches the images and downloadable files of the latest response through the page, as base64
+// @param responseSelectors - Candidate response selectors, in order
+// @param excludeSelectors - UI chrome whose images and links are ignored
+// @param maxBytes - Larger files are reported without their data
+
+let last = null;
+for (const selector of args.responseSelectors) {
+    let elements = [];
+    try { elements = Array.from(document.querySelectorAll(selector)); } catch (e) { continue; }
+    if (elements.length > 0) { last = elements[elements.length - 1]; break; }
+}
+if (!last) return [];
+
+const excluded = (el) => args.excludeSelectors.some((selector) => {
+    try { return el.closest(selector) !== null; } catch (e) { return false; }
+});
+
+const found = [];
+const seen = new Set();
+for (const img of last.querySelectorAll('img')) {
+    const url = img.currentSrc || img.src;
+    // Favicons of cited sites and other icons
+    const icon = img.naturalWidth > 0 && img.naturalWidth < 64 && img.naturalHeight < 64;
+    if (!url || icon || excluded(img) || seen.has(url)) continue;
+    seen.add(url);
+    found.push({ kind: 'image', url, name: null, alt: img.alt || null });
+}
+for (const link of last.querySelectorAll('a[href]')) {
+    const url = link.href;
+    const download = link.hasAttribute('download') || url.startsWith('blob:') || url.startsWith('data:');
+    if (!download || excluded(link) || seen.has(url)) continue;
+    seen.add(url);
+    const name = link.getAttribute('download') || (link.innerText || link.textContent || '').trim();
+    found.push({ kind: 'file', url, name: name || null, alt: null });
+}
+
+const toBase64 = (blob) => new Promise((resolve, reject) => {
+    const reader = new FileReader();
+    reader.onload = () => resolve(String(reader.result).split(',')[1] || '');
+    reader.onerror = () => reject(reader.error);
+    reader.readAsDataURL(blob);
+});
+
+// The page's cookies authorize service-hosted files
+const results = [];
+for (const item of found) {
+    try {
+        const response = await fetch(item.url, { credentials: 'include' });
+        if (!response.ok) throw new Error('HTTP ' + response.status);
+        const blob = await response.blob();
+        if (blob.size > args.maxBytes) throw new Error('larger than ' + args.maxBytes + ' bytes');
+        const type = blob.type || response.headers.get('content-type');
+        results.push({ ...item, content_type: type || null, data: await toBase64(blob), error: null });
+    } catch (e) {
+        results.push({ ...item, content_type: null, data: null, error: String((e && e.message) || e) });
+    }
+}
+return results;
@@ -0,0 +1,39 @@
+// @version 1.1.0
+// @description Reads the latest response text and the streaming, stop-button and send-button state
+// @param responseSelectors - Candidate response selectors, in order
+// @param excludeSelectors - UI chrome removed from a copy of the response before reading it
+// @param streamingSelectors - Elements present while the answer streams
+// @param stopSelectors - Stop-generating buttons
+// @param sendSelectors - Send buttons, enabled again when the answer is done
+
+const query = (selector) => {
+    try { return Array.from(document.querySelectorAll(selector)); } catch (e) { return []; }
+};
+const shown = (el) => el.offsetParent !== null;
+// Copy of the element without the service's UI chrome
+const clean = (el) => {
+    const copy = el.cloneNode(true);
+    for (const selector of args.excludeSelectors) {
+        try { copy.querySelectorAll(selector).forEach((node) => node.remove()); } catch (e) { /* invalid selector */ }
+    }
+    return copy;
+};
+
+let last = null;
+for (const selector of args.responseSelectors) {
+    const responses = query(selector);
+    if (responses.length > 0) { last = clean(responses[responses.length - 1]); break; }
+}
+
+let send = null;
+for (const selector of args.sendSelectors) {
+    send = query(selector).find(shown);
+    if (send) break;
+}
+
+return {
+    text: last ? (last.innerText || last.textContent || '') : '',
+    streaming: args.streamingSelectors.some((selector) => query(selector).length > 0),
+    stop_visible: args.stopSelectors.some((selector) => query(selector).some(shown)),
+    send_enabled: send ? !(send.disabled || send.getAttribute('aria-disabled') === 'true') : null
+};
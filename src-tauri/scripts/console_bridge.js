@@ -0,0 +1,43 @@
+// @version 1.0.0
+// @description Forwards console output, errors and unhandled rejections to webview_console_report
+
+if (window.__4AI_CONSOLE_BRIDGE) return;
+window.__4AI_CONSOLE_BRIDGE = true;
+
+const MAX_LENGTH = 4000;
+const queue = [];
+const original = {};
+
+const describe = (value) => {
+    if (typeof value === 'string') return value;
+    if (value instanceof Error) return value.stack || value.message;
+    try { return JSON.stringify(value); } catch (e) { return String(value); }
+};
+
+const enqueue = (level, args, source) => {
+    const message = Array.from(args).map(describe).join(' ').substring(0, MAX_LENGTH);
+    queue.push({ level, message, source: source || null, timestamp: Date.now() });
+    if (queue.length > 200) queue.splice(0, queue.length - 200);
+};
+
+['debug', 'log', 'info', 'warn', 'error'].forEach((level) => {
+    original[level] = console[level].bind(console);
+    console[level] = function() {
+        enqueue(level, arguments);
+        return original[level].apply(console, arguments);
+    };
+});
+
+window.addEventListener('error', (event) => {
+    const source = event.filename ? event.filename + ':' + event.lineno + ':' + event.colno : null;
+    enqueue('error', ['Uncaught ' + (event.error ? describe(event.error) : event.message)], source);
+});
+window.addEventListener('unhandledrejection', (event) => {
+    enqueue('error', ['Unhandled rejection: ' + describe(event.reason)]);
+});
+
+setInterval(() => {
+    if (!queue.length || typeof window.__TAURI_INVOKE__ !== 'function') return;
+    const entries = queue.splice(0, queue.length);
+    window.__TAURI_INVOKE__('webview_console_report', { entries }).catch(() => {});
+}, 1000);
@@ -0,0 +1,17 @@
+// @version 1.0.0
+// @description Returns one chunk of the content stored by extract_monitored
+// @param index - Zero-based chunk index
+
+console.log('[4AI CHUNK] Retrieving chunk', args.index);
+
+if (window.__4AI_CONTENT_CHUNKS && window.__4AI_CONTENT_CHUNKS.length > args.index) {
+    const chunk = window.__4AI_CONTENT_CHUNKS[args.index];
+    console.log('[4AI CHUNK] Found chunk', args.index, 'length:', chunk.length);
+    return chunk;
+} else if (window.__4AI_EXTRACTED_CONTENT) {
+    console.log('[4AI CHUNK] No chunks, returning full content');
+    return window.__4AI_EXTRACTED_CONTENT;
+} else {
+    console.log('[4AI CHUNK] No content available');
+    return 'NO_CONTENT';
+}
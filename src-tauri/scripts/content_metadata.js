@@ -0,0 +1,13 @@
+// @version 1.0.0
+// @description Describes the extracted content and chunks stored in the page, as a JSON string
+
+const metadata = {
+    hasContent: !!window.__4AI_EXTRACTED_CONTENT,
+    contentLength: window.__4AI_EXTRACTED_CONTENT ? window.__4AI_EXTRACTED_CONTENT.length : 0,
+    hasChunks: !!window.__4AI_CONTENT_CHUNKS,
+    chunkCount: window.__4AI_CONTENT_CHUNKS ? window.__4AI_CONTENT_CHUNKS.length : 0,
+    hasMetadata: !!window.__4AI_CONTENT_METADATA
+};
+
+console.log('[4AI META]', metadata);
+return JSON.stringify(metadata);
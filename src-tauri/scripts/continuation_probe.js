@@ -0,0 +1,58 @@
+// @version 1.2.0
+// @description Reads the latest response and finds a "continue generating" button, optionally clicking it
+// @param responseSelectors - Candidate response selectors, in order
+// @param excludeSelectors - UI chrome removed from a copy of the response before reading it
+// @param buttonSelectors - Continue button selectors
+// @param buttonTexts - Exact continue button labels (case-insensitive)
+// @param generatingSelectors - Elements present while the answer streams
+// @param click - Click the continue button when one is found
+
+const visible = (el) => el && el.offsetParent !== null && !el.disabled && el.getAttribute('aria-disabled') !== 'true';
+const query = (selector) => {
+    try { return Array.from(document.querySelectorAll(selector)); } catch (e) { return []; }
+};
+// Copy of the element without the service's UI chrome
+const clean = (el) => {
+    const copy = el.cloneNode(true);
+    for (const selector of args.excludeSelectors) {
+        try { copy.querySelectorAll(selector).forEach((node) => node.remove()); } catch (e) { /* invalid selector */ }
+    }
+    return copy;
+};
+
+let responses = [];
+for (const selector of args.responseSelectors) {
+    responses = query(selector);
+    if (responses.length > 0) break;
+}
+const last = responses.length > 0 ? clean(responses[responses.length - 1]) : null;
+
+let button = null;
+let matched = null;
+for (const selector of args.buttonSelectors) {
+    button = query(selector).find(visible);
+    if (button) { matched = selector; break; }
+}
+if (!button && args.buttonTexts.length > 0) {
+    const labels = args.buttonTexts.map((text) => text.toLowerCase());
+    button = query('button, [role="button"]').find((el) =>
+        visible(el) && labels.includes((el.innerText || el.textContent || '').trim().toLowerCase()));
+    if (button) matched = 'text:' + (button.innerText || button.textContent).trim();
+}
+
+const generating = args.generatingSelectors.some((selector) => query(selector).some((el) => el.offsetParent !== null));
+
+let clicked = false;
+if (args.click && button) {
+    button.click();
+    clicked = true;
+}
+
+return {
+    text: last ? (last.innerText || last.textContent || '') : '',
+    html: last ? last.innerHTML : '',
+    count: responses.length,
+    continue_selector: matched,
+    generating,
+    clicked
+};
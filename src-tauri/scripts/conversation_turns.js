@@ -0,0 +1,37 @@
+// @version 1.1.0
+// @description Lists the user and assistant messages of the open conversation in page order
+// @param userSelectors - Candidate user message selectors, in order
+// @param assistantSelectors - Candidate assistant message selectors, in order
+// @param excludeSelectors - UI chrome removed from a copy of the element before reading it
+
+const query = (selector) => {
+    try { return Array.from(document.querySelectorAll(selector)); } catch (e) { return []; }
+};
+const firstMatch = (selectors) => {
+    for (const selector of selectors) {
+        const found = query(selector);
+        if (found.length > 0) return found;
+    }
+    return [];
+};
+// Copy of the element without the service's UI chrome
+const clean = (el) => {
+    const copy = el.cloneNode(true);
+    for (const selector of args.excludeSelectors) {
+        try { copy.querySelectorAll(selector).forEach((node) => node.remove()); } catch (e) { /* invalid selector */ }
+    }
+    return copy;
+};
+
+const turns = [
+    ...firstMatch(args.userSelectors).map((el) => ({ role: 'user', el })),
+    ...firstMatch(args.assistantSelectors).map((el) => ({ role: 'assistant', el }))
+];
+// A selector matching both a message and its content keeps the outer element
+const outer = turns.filter((turn) => !turns.some((other) => other !== turn && other.el.contains(turn.el)));
+outer.sort((a, b) => (a.el.compareDocumentPosition(b.el) & Node.DOCUMENT_POSITION_FOLLOWING ? -1 : 1));
+
+return outer.map((turn) => {
+    const copy = clean(turn.el);
+    return { role: turn.role, html: copy.innerHTML, text: (copy.innerText || copy.textContent || '').trim() };
+});
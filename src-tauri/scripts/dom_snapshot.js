@@ -0,0 +1,21 @@
+// @version 1.0.0
+// @description Serializes the page and counts matches for every configured selector
+// @param groups - [group, selectors[]] pairs to count
+// @param maxHtmlLength - Longest outerHTML returned
+
+const selectors = [];
+for (const [group, list] of args.groups) {
+    for (const selector of list) {
+        let count = 0;
+        try { count = document.querySelectorAll(selector).length; } catch (e) { count = 0; }
+        selectors.push({ group, selector, count });
+    }
+}
+const html = document.documentElement ? document.documentElement.outerHTML : '';
+return {
+    url: location.href,
+    title: document.title,
+    html: html.substring(0, args.maxHtmlLength),
+    truncated: html.length > args.maxHtmlLength,
+    selectors
+};
@@ -0,0 +1,17 @@
+// @version 1.0.0
+// @description Reports visible elements matching each error signature's selectors
+// @param groups - Selector lists, one per signature in config order
+
+const hits = [];
+args.groups.forEach((selectors, signature) => {
+    for (const selector of selectors) {
+        let elements = [];
+        try { elements = Array.from(document.querySelectorAll(selector)); } catch (e) { continue; }
+        const el = elements.find((candidate) => candidate.offsetParent !== null);
+        if (el) {
+            hits.push({ signature, selector, text: (el.innerText || el.textContent || '').trim().substring(0, 1000) });
+            break;
+        }
+    }
+});
+return hits;
@@ -0,0 +1,62 @@
+// @version 1.0.0
+// @description Finds the longest response text with built-in per-service selectors
+// @param serviceId - Service whose selectors are tried
+
+try {
+    console.log('[EXTRACTION] Starting content extraction for', args.serviceId);
+
+    // Enhanced selectors for each service
+    const serviceSelectors = {
+      'claude': [
+        'div[class*="font-claude"]',
+        '[data-testid="conversation"] div[data-testid*="message"] div:last-child',
+        '.font-claude-message',
+        '[role="presentation"] div:last-child p',
+        '[data-is-streaming="false"] .font-claude-message'
+      ],
+      'chatgpt': [
+        '[data-message-author-role="assistant"] .markdown',
+        '.prose',
+        '[data-testid*="conversation"] div:last-child',
+        '[role="assistant"] .markdown'
+      ],
+      'gemini': [
+        '[data-response-index] .markdown',
+        '.model-response-text',
+        '.response-container',
+        '.model-response'
+      ]
+    };
+
+    const selectors = Object.prototype.hasOwnProperty.call(serviceSelectors, args.serviceId) ? serviceSelectors[args.serviceId] : [];
+    let bestContent = '';
+
+    for (const selector of selectors) {
+        console.log('[EXTRACTION] Trying selector:', selector);
+        const elements = document.querySelectorAll(selector);
+
+        for (const element of elements) {
+            const text = (element.textContent || element.innerText || '').trim();
+            if (text.length > bestContent.length && text.length > 10) {
+                bestContent = text;
+                console.log('[EXTRACTION] Found better content:', selector, 'length:', text.length);
+            }
+        }
+    }
+
+    if (bestContent.length > 0) {
+        console.log('[EXTRACTION] Final content length:', bestContent.length);
+        window.__4AI_EXTRACTED_FINAL = bestContent;
+        document.title = '[EXTRACTED]' + bestContent.length;
+        return bestContent;
+    } else {
+        console.log('[EXTRACTION] No content found with any selector');
+        document.title = '[NO_CONTENT]';
+        return '';
+    }
+
+} catch (error) {
+    console.error('[EXTRACTION] Error:', error);
+    document.title = '[EXTRACTION_ERROR]';
+    return '';
+}
@@ -0,0 +1,331 @@
+// @version 1.0.0
+// @description Extracts the response with monitor, cache and DOM fallbacks, then chunks it
+
+let extractedContent = '';
+let extractionMethod = 'none';
+let contentMetadata = {};
+
+console.log('[4AI EXTRACT] ========== STARTING COMPREHENSIVE EXTRACTION ==========');
+
+// Strategy 1: Monitor-based extraction (highest priority)
+if (window.__4AI_FINAL_RESPONSE && window.__4AI_FINAL_RESPONSE.completed) {
+    const response = window.__4AI_FINAL_RESPONSE;
+    console.log('[4AI EXTRACT] ✅ PRIORITY 1: Monitor-based extraction');
+    console.log('[4AI EXTRACT] Response details:', {
+        length: response.text.length,
+        serviceId: response.serviceId,
+        reason: response.reason,
+        stable: response.stable
+    });
+
+    extractedContent = response.text;
+    extractionMethod = 'monitor_' + (response.serviceId || 'unknown');
+    contentMetadata = {
+        serviceId: response.serviceId,
+        reason: response.reason,
+        stable: response.stable,
+        detectionTime: response.detectionTime,
+        source: 'monitor'
+    };
+
+    // Clear to prevent reuse
+    window.__4AI_FINAL_RESPONSE = null;
+}
+
+// Strategy 2: Cached extraction content
+else if (window.__4AI_EXTRACTED_CONTENT) {
+    const content = window.__4AI_EXTRACTED_CONTENT;
+    console.log('[4AI EXTRACT] ✅ PRIORITY 2: Cache-based extraction:', content.length, 'chars');
+    extractedContent = content;
+    extractionMethod = 'cache';
+    contentMetadata.source = 'cache';
+    window.__4AI_EXTRACTED_CONTENT = null;
+}
+
+// Strategy 3: Fallback content from timeout scenarios
+else if (window.__4AI_FALLBACK_CONTENT) {
+    const content = window.__4AI_FALLBACK_CONTENT;
+    console.log('[4AI EXTRACT] ✅ PRIORITY 3: Fallback extraction:', content.length, 'chars');
+    extractedContent = content;
+    extractionMethod = 'fallback';
+    contentMetadata.source = 'fallback';
+    window.__4AI_FALLBACK_CONTENT = null;
+}
+
+// Strategy 4: Advanced service-specific DOM extraction
+else {
+    console.log('[4AI EXTRACT] ⚠️ PRIORITY 4: Advanced DOM extraction required');
+
+    // Claude-specific advanced selectors
+    const claudeSelectors = [
+        // Streaming and completed message selectors
+        '[data-is-streaming="false"] .font-claude-message:last-child',
+        '.font-claude-message:last-of-type',
+        '[aria-label*="Claude"] .ProseMirror[contenteditable="false"]:last-child',
+        '.ProseMirror[contenteditable="false"]:not([data-is-streaming="true"]):last-child',
+        // Conversation thread selectors
+        '[data-testid*="message"]:last-child .font-claude-message',
+        '.claude-chat-message:last-child .font-claude-message',
+        // Generic fallbacks for Claude
+        '.font-claude-message'
+    ];
+
+    // ChatGPT-specific advanced selectors
+    const chatgptSelectors = [
+        // Latest assistant message
+        '[data-message-author-role="assistant"]:last-child .markdown.prose',
+        '[data-message-author-role="assistant"]:last-child .whitespace-pre-wrap',
+        '.group\\/conversation-turn:last-child [data-message-author-role="assistant"] .markdown',
+        // Conversation structure
+        '[data-testid*="conversation-turn"]:last-child .markdown',
+        '.prose:last-child',
+        // Generic ChatGPT fallbacks
+        '[data-message-author-role="assistant"] .markdown'
+    ];
+
+    // Gemini-specific advanced selectors
+    const geminiSelectors = [
+        // Response containers
+        '.model-response-text:last-child',
+        '[data-response-chunk]:last-child .rich-text-formatted',
+        '.response-container:last-child .formatted-text',
+        '.bard-response:last-child .response-text',
+        // Generic Gemini structures
+        '.rich-text-formatted:last-child',
+        '.model-response-text'
+    ];
+
+    // Universal advanced selectors (last resort)
+    const universalSelectors = [
+        '[role="assistant"]:last-child',
+        '.assistant-response:last-child .content',
+        '.ai-response:last-child .text',
+        '[data-role="assistant"]:last-child',
+        '.response:last-child .body',
+        '.message:last-child .content',
+        // Very generic fallbacks
+        '.message-content:last-child',
+        '.response-text:last-child'
+    ];
+
+    // Combine all selectors with priority order
+    const allSelectors = [
+        ...claudeSelectors,
+        ...chatgptSelectors,
+        ...geminiSelectors,
+        ...universalSelectors
+    ];
+
+    console.log('[4AI EXTRACT] Trying', allSelectors.length, 'advanced selectors...');
+
+    for (let i = 0; i < allSelectors.length; i++) {
+        const selector = allSelectors[i];
+        try {
+            const elements = document.querySelectorAll(selector);
+            console.log('[4AI EXTRACT] Selector', i + 1, ':', selector, '→', elements.length, 'elements');
+
+            for (let j = 0; j < elements.length; j++) {
+                const elem = elements[j];
+                if (elem && elem.textContent) {
+                    const rawContent = elem.textContent.trim();
+
+                    // Advanced content quality validation
+                    const contentChecks = {
+                        hasMinLength: rawContent.length >= 50,
+                        hasMaxLength: rawContent.length <= 100000,
+                        hasPunctuation: /[.!?]/.test(rawContent),
+                        hasWords: rawContent.split(/\s+/).length >= 5,
+                        notUIText: !(/^(send|submit|copy|share|like|dislike|regenerate)$/i.test(rawContent.toLowerCase())),
+                        hasStructure: rawContent.includes('\n') || /[.!?]\s+[A-Z]/.test(rawContent),
+                        notErrorMessage: !/(error|failed|loading|please wait)/i.test(rawContent.substring(0, 50))
+                    };
+
+                    const qualityScore = Object.values(contentChecks).filter(Boolean).length;
+                    const isHighQuality = qualityScore >= 5;
+
+                    console.log('[4AI EXTRACT] Element', j + 1, 'quality check:', {
+                        length: rawContent.length,
+                        score: qualityScore + '/7',
+                        preview: rawContent.substring(0, 100) + '...',
+                        checks: contentChecks
+                    });
+
+                    if (isHighQuality) {
+                        extractedContent = rawContent;
+                        extractionMethod = 'advanced_dom_' + (i + 1);
+                        contentMetadata = {
+                            selector: selector,
+                            elementIndex: j,
+                            qualityScore: qualityScore,
+                            element: elem.tagName + (elem.className ? '.' + elem.className.split(' ').slice(0, 2).join('.') : ''),
+                            source: 'advanced_dom'
+                        };
+
+                        console.log('[4AI EXTRACT] ✅ HIGH QUALITY CONTENT FOUND:', {
+                            method: extractionMethod,
+                            length: rawContent.length,
+                            selector: selector.substring(0, 50)
+                        });
+                        break;
+                    }
+                }
+            }
+            if (extractedContent) break;
+        } catch (error) {
+            console.warn('[4AI EXTRACT] Selector error:', selector, error.message);
+        }
+    }
+}
+
+// Strategy 5: Content processing and optimization
+if (extractedContent && extractedContent.length > 0) {
+    console.log('[4AI EXTRACT] ========== CONTENT PROCESSING ==========');
+
+    // Advanced content cleanup
+    let processedContent = extractedContent
+        // Remove AI service name prefixes
+        .replace(/^(Claude|ChatGPT|Gemini|Bard|Assistant)[\s:]+/i, '')
+        // Normalize whitespace
+        .replace(/\n\s*\n\s*\n/g, '\n\n')
+        .replace(/^\s+|\s+$/g, '')
+        // Replace special characters
+        .replace(/\u00A0/g, ' ') // Non-breaking spaces
+        .replace(/[\u200B-\u200D\uFEFF]/g, '') // Zero-width characters
+        .replace(/\u2026/g, '...') // Ellipsis
+        // Remove common UI artifacts
+        .replace(/^(Copy|Share|Like|Dislike|Regenerate)\s*/gm, '')
+        // Clean up markdown artifacts if needed
+        .replace(/\*\*(.*?)\*\*/g, '$1') // Bold
+        .replace(/\*(.*?)\*/g, '$1'); // Italic
+
+    // Advanced content analysis
+    const analysis = {
+        originalLength: extractedContent.length,
+        processedLength: processedContent.length,
+        wordCount: processedContent.split(/\s+/).length,
+        sentenceCount: (processedContent.match(/[.!?]+/g) || []).length,
+        paragraphCount: processedContent.split(/\n\s*\n/).length,
+        hasCodeBlocks: /```/.test(processedContent),
+        hasLists: /^\s*[-*+•]\s/m.test(processedContent),
+        hasNumbers: /\d+/.test(processedContent),
+        language: /[ąćęłńóśźż]/i.test(processedContent) ? 'polish' : 'english',
+        complexity: processedContent.split(/\s+/).length > 100 ? 'high' : 
+                   processedContent.split(/\s+/).length > 30 ? 'medium' : 'low'
+    };
+
+    // Content quality assessment
+    const qualityIndicators = {
+        hasGoodLength: analysis.processedLength >= 30 && analysis.processedLength <= 50000,
+        hasStructure: analysis.sentenceCount >= 2 || analysis.paragraphCount >= 2,
+        hasVariety: analysis.hasCodeBlocks || analysis.hasLists || analysis.hasNumbers,
+        isComplete: !processedContent.endsWith('...') && !processedContent.toLowerCase().includes('loading'),
+        isCoherent: analysis.wordCount >= 10 && analysis.sentenceCount >= 1
+    };
+
+    const qualityScore = Object.values(qualityIndicators).filter(Boolean).length;
+    const overallQuality = qualityScore >= 4 ? 'excellent' : qualityScore >= 3 ? 'good' : 'acceptable';
+
+    // Combine metadata
+    contentMetadata = {
+        ...contentMetadata,
+        ...analysis,
+        qualityIndicators,
+        qualityScore,
+        overallQuality,
+        extractionMethod,
+        timestamp: Date.now()
+    };
+
+    extractedContent = processedContent;
+
+    console.log('[4AI EXTRACT] ========== EXTRACTION COMPLETE ==========');
+    console.log('[4AI EXTRACT] Final analysis:', {
+        method: extractionMethod,
+        length: processedContent.length,
+        words: analysis.wordCount,
+        quality: overallQuality,
+        language: analysis.language
+    });
+}
+
+// ========== RESULT PREPARATION AND CHUNKING ==========
+if (extractedContent && extractedContent.length > 0) {
+    console.log('[4AI EXTRACT] ========== PREPARING RESULTS ==========');
+
+    // Smart chunking based on content size and structure
+    const maxChunkSize = extractedContent.length <= 5000 ? 800 : 
+                       extractedContent.length <= 20000 ? 1000 : 1200;
+
+    const chunks = [];
+    let currentPosition = 0;
+
+    // Try to chunk at natural boundaries (paragraphs, sentences)
+    while (currentPosition < extractedContent.length) {
+        let chunkEnd = Math.min(currentPosition + maxChunkSize, extractedContent.length);
+
+        // Look for natural break points within reasonable distance
+        if (chunkEnd < extractedContent.length) {
+            const searchStart = Math.max(chunkEnd - 100, currentPosition + maxChunkSize - 200);
+
+            // Try paragraph break first
+            let breakPoint = extractedContent.lastIndexOf('\n\n', chunkEnd);
+            if (breakPoint > searchStart) {
+                chunkEnd = breakPoint + 2;
+            }
+            // Try sentence break
+            else {
+                breakPoint = extractedContent.search(/[.!?]\s+/g);
+                let lastSentenceEnd = -1;
+                while (breakPoint !== -1 && breakPoint <= chunkEnd) {
+                    lastSentenceEnd = breakPoint + extractedContent.match(/[.!?]\s+/g)[0].length;
+                    breakPoint = extractedContent.indexOf(breakPoint + 1);
+                }
+                if (lastSentenceEnd > searchStart && lastSentenceEnd <= chunkEnd) {
+                    chunkEnd = lastSentenceEnd;
+                }
+            }
+        }
+
+        const chunk = extractedContent.substring(currentPosition, chunkEnd);
+        chunks.push(chunk);
+        currentPosition = chunkEnd;
+    }
+
+    // Store comprehensive results
+    window.__4AI_CONTENT_RESULT = extractedContent;
+    window.__4AI_CONTENT_CHUNKS = chunks;
+    window.__4AI_CHUNK_COUNT = chunks.length;
+    window.__4AI_CONTENT_METADATA = contentMetadata;
+
+    // Prepare success signal with rich metadata
+    const signalData = {
+        length: extractedContent.length,
+        chunks: chunks.length,
+        method: extractionMethod,
+        quality: contentMetadata.overallQuality || 'unknown',
+        words: contentMetadata.wordCount || 0,
+        language: contentMetadata.language || 'unknown',
+        timestamp: Date.now()
+    };
+
+    console.log('[4AI EXTRACT] ✅ EXTRACTION SUCCESSFUL:', signalData);
+    console.log('[4AI EXTRACT] Content preview:', extractedContent.substring(0, 200) + '...');
+    console.log('[4AI EXTRACT] Metadata:', contentMetadata);
+
+    // Enhanced success signal
+    document.title = '[4AI_EXTRACT_SUCCESS]' + 
+        signalData.length + '_' + 
+        signalData.chunks + '_' + 
+        signalData.method + '_' + 
+        signalData.quality + '_' + 
+        signalData.timestamp;
+
+    return true;
+} else {
+    console.log('[4AI EXTRACT] ❌ NO CONTENT EXTRACTED');
+    console.log('[4AI EXTRACT] Available window properties:', Object.keys(window).filter(k => k.includes('4AI')));
+
+    // Enhanced failure signal
+    document.title = '[4AI_EXTRACT_EMPTY]' + extractionMethod + '_' + Date.now();
+    return false;
+}
@@ -0,0 +1,30 @@
+// @version 1.2.0
+// @description Returns the HTML and text of the latest response element
+// @param responseSelectors - Candidate response selectors, in order
+// @param excludeSelectors - UI chrome removed from a copy of the element before reading it
+
+// Copy of the element without the service's UI chrome
+const clean = (el) => {
+    const copy = el.cloneNode(true);
+    for (const selector of args.excludeSelectors) {
+        try { copy.querySelectorAll(selector).forEach((node) => node.remove()); } catch (e) { /* invalid selector */ }
+    }
+    return copy;
+};
+
+for (const [index, selector] of args.responseSelectors.entries()) {
+    // Named like extract_monitored's methods; later selectors are fallbacks
+    const extractionMethod = 'dom_selector_' + (index + 1);
+    let elements = [];
+    try { elements = Array.from(document.querySelectorAll(selector)); } catch (e) { continue; }
+    if (elements.length === 0) continue;
+    const last = clean(elements[elements.length - 1]);
+    return {
+        selector,
+        extraction_method: extractionMethod,
+        count: elements.length,
+        html: last.innerHTML,
+        text: (last.innerText || last.textContent || '').trim()
+    };
+}
+return null;
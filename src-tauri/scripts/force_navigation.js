@@ -0,0 +1,10 @@
+// @version 1.0.0
+// @description Loads the target URL when a new window is stuck on about:blank
+// @param url - URL the window should show
+
+// New windows sometimes stay on about:blank instead of loading their URL
+if (window.location.href === 'about:blank' || window.location.href === '') {
+    window.location.replace(args.url);
+    return true;
+}
+return false;
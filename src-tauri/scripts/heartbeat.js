@@ -0,0 +1,4 @@
+// @version 1.0.0
+// @description Watchdog heartbeat; returns the page clock
+
+return Date.now();
@@ -0,0 +1,218 @@
+// @version 1.1.0
+// @description Fills the service input with the prompt (at once or typed) and sends it
+// @param prompt - Text to send
+// @param mode - 'instant' or 'typing'
+// @param strict - Do not send unless the input holds exactly the prompt
+// @param strategy - InputStrategy from the selector config
+// @param typing - {chunkSize, chunkDelayMs, jitterMs, newline} for typing mode
+// @param inputSelectors - Candidate input selectors, in order
+// @param sendSelectors - Candidate send button selectors, in order
+// @param sendDelayMs - Pause before looking for the send button
+
+const sleep = (ms) => new Promise((resolve) => setTimeout(resolve, ms));
+const normalize = (text) => (text || '').replace(/\s+/g, ' ').trim();
+const isValueInput = (el) => el.tagName === 'TEXTAREA' || el.tagName === 'INPUT';
+
+const detect = (el) => {
+    if (el.classList.contains('ProseMirror') || el.closest('.ProseMirror')) return 'prosemirror';
+    if (el.classList.contains('ql-editor')) return 'quill';
+    if (isValueInput(el)) {
+        return Object.keys(el).some((key) => key.startsWith('__react')) ? 'react' : 'textarea';
+    }
+    if (el.isContentEditable) return 'contenteditable';
+    return null;
+};
+
+// A configured strategy only applies to elements of the right kind
+const strategyFor = (el) => {
+    const wanted = args.strategy;
+    if (wanted === 'auto') return detect(el);
+    const wantsValue = wanted === 'textarea' || wanted === 'react';
+    if (wantsValue === isValueInput(el) && (wantsValue || el.isContentEditable)) return wanted;
+    return detect(el);
+};
+
+const selectContents = (el) => {
+    const range = document.createRange();
+    range.selectNodeContents(el);
+    const selection = window.getSelection();
+    selection.removeAllRanges();
+    selection.addRange(range);
+};
+
+const fire = (el, text) => {
+    el.dispatchEvent(new InputEvent('input', { bubbles: true, inputType: 'insertText', data: text }));
+    el.dispatchEvent(new Event('change', { bubbles: true }));
+};
+
+const fill = {
+    textarea(el, text) {
+        el.value = text;
+        fire(el, text);
+    },
+    react(el, text) {
+        const proto = el.tagName === 'TEXTAREA' ? HTMLTextAreaElement.prototype : HTMLInputElement.prototype;
+        Object.getOwnPropertyDescriptor(proto, 'value').set.call(el, text);
+        fire(el, text);
+    },
+    contenteditable(el, text) {
+        selectContents(el);
+        if (!document.execCommand('insertText', false, text)) {
+            el.textContent = text;
+            fire(el, text);
+        }
+    },
+    prosemirror(el, text) {
+        selectContents(el);
+        const data = new DataTransfer();
+        data.setData('text/plain', text);
+        const paste = new ClipboardEvent('paste', { clipboardData: data, bubbles: true, cancelable: true });
+        // ProseMirror cancels the paste event when it handled it
+        if (el.dispatchEvent(paste)) {
+            document.execCommand('insertText', false, text);
+        }
+    },
+    quill(el, text) {
+        el.replaceChildren(...text.split('\n').map((line) => {
+            const p = document.createElement('p');
+            if (line) p.textContent = line; else p.appendChild(document.createElement('br'));
+            return p;
+        }));
+        fire(el, text);
+    }
+};
+
+const NEWLINE_KEYS = {
+    shift_enter: { shiftKey: true },
+    enter: {},
+    ctrl_enter: { ctrlKey: true }
+};
+
+const key = (el, type, name, modifiers = {}) => el.dispatchEvent(new KeyboardEvent(type, {
+    key: name,
+    code: name === 'Enter' ? 'Enter' : undefined,
+    keyCode: name === 'Enter' ? 13 : name.charCodeAt(0),
+    bubbles: true,
+    cancelable: true,
+    ...modifiers
+}));
+
+const clear = (el, strategy) => {
+    if (isValueInput(el)) {
+        fill[strategy === 'react' ? 'react' : 'textarea'](el, '');
+    } else {
+        selectContents(el);
+        document.execCommand('delete');
+    }
+};
+
+const insert = (el, strategy, text) => {
+    if (!isValueInput(el)) {
+        document.execCommand('insertText', false, text);
+        return;
+    }
+    const value = el.value + text;
+    if (strategy === 'react') {
+        const proto = el.tagName === 'TEXTAREA' ? HTMLTextAreaElement.prototype : HTMLInputElement.prototype;
+        Object.getOwnPropertyDescriptor(proto, 'value').set.call(el, value);
+    } else {
+        el.value = value;
+    }
+    el.dispatchEvent(new InputEvent('input', { bubbles: true, inputType: 'insertText', data: text }));
+};
+
+// Editors bind line breaks to a modifier+Enter; the default action is the fallback
+const typeNewline = (el, strategy) => {
+    const modifiers = NEWLINE_KEYS[args.typing.newline] || NEWLINE_KEYS.shift_enter;
+    const handled = !key(el, 'keydown', 'Enter', modifiers);
+    if (!handled) {
+        if (isValueInput(el)) insert(el, strategy, '\n');
+        else document.execCommand(strategy === 'quill' ? 'insertParagraph' : 'insertLineBreak');
+    }
+    key(el, 'keyup', 'Enter', modifiers);
+};
+
+const typeChar = (el, strategy, char) => {
+    if (!key(el, 'keydown', char)) return;
+    key(el, 'keypress', char);
+    insert(el, strategy, char);
+    key(el, 'keyup', char);
+};
+
+const type = async (el, strategy, text) => {
+    clear(el, strategy);
+    const chars = Array.from(text.replace(/\r\n?/g, '\n'));
+    const size = Math.max(1, args.typing.chunkSize);
+    for (let i = 0; i < chars.length; i += size) {
+        for (const char of chars.slice(i, i + size)) {
+            if (char === '\n') typeNewline(el, strategy);
+            else typeChar(el, strategy, char);
+        }
+        await sleep(args.typing.chunkDelayMs + Math.random() * args.typing.jitterMs);
+    }
+};
+
+const readBack = (el) => (isValueInput(el) ? el.value : el.innerText);
+const expected = normalize(args.prompt);
+// Typed text must match exactly; instant fills only need the beginning to be present
+const matches = (el, mode) => {
+    const actual = normalize(readBack(el));
+    return mode === 'typing' || args.strict ? actual === expected : actual.includes(expected.substring(0, 200));
+};
+
+let used = null;
+for (const selector of args.inputSelectors) {
+    let elements = [];
+    try { elements = Array.from(document.querySelectorAll(selector)); } catch (e) { continue; }
+    // The composer is usually the last match
+    const el = elements.reverse().find((candidate) => candidate.offsetParent !== null || candidate === document.activeElement) || elements[0];
+    if (!el) continue;
+    const strategy = strategyFor(el);
+    if (!strategy) continue;
+
+    el.focus();
+    let mode = args.mode;
+    if (mode === 'typing') {
+        await type(el, strategy, args.prompt);
+    } else {
+        fill[strategy](el, args.prompt);
+    }
+    await sleep(50);
+    let verified = matches(el, mode);
+    if (!verified && mode === 'typing') {
+        // The editor dropped keystrokes; fall back to a one-step fill
+        console.warn('[4AI INJECT] typed text does not match, filling instead');
+        mode = 'instant';
+        fill[strategy](el, args.prompt);
+        await sleep(50);
+        verified = matches(el, mode);
+    }
+    console.log('[4AI INJECT] ' + selector + ' via ' + strategy + ' (' + mode + ')' + (verified ? ' (verified)' : ' (not verified)'));
+    used = { el, selector, strategy, mode, verified };
+    if (verified) break;
+}
+if (!used) {
+    throw new Error('No input matched: ' + args.inputSelectors.join(', '));
+}
+if (args.strict && !used.verified) {
+    throw new Error('Input content does not match the prompt; not sending');
+}
+
+await sleep(args.sendDelayMs);
+const enabled = (button) => button && !button.disabled && button.getAttribute('aria-disabled') !== 'true';
+// React enables the send button a moment after the input event
+for (let attempt = 0; attempt < 10; attempt++) {
+    for (const selector of args.sendSelectors) {
+        let button = null;
+        try { button = document.querySelector(selector); } catch (e) { continue; }
+        if (enabled(button)) {
+            button.click();
+            return { mode: used.mode, strategy: used.strategy, input_selector: used.selector, send_selector: selector, sent_with: 'click', verified: used.verified };
+        }
+    }
+    await sleep(300);
+}
+
+used.el.focus();
+used.el.dispatchEvent(new KeyboardEvent('keydown', { key: 'Enter', code: 'Enter', keyCode: 13, which: 13, bubbles: true, cancelable: true }));
+return { mode: used.mode, strategy: used.strategy, input_selector: used.selector, send_selector: null, sent_with: 'enter', verified: used.verified };
@@ -0,0 +1,26 @@
+// @version 1.0.0
+// @description Classifies the page as logged in, logged out or behind a challenge
+// @param challengeSelectors - Captcha and interstitial selectors
+// @param loggedOutSelectors - Selectors only present when signed out
+// @param loggedInSelectors - Selectors only present when signed in
+
+const groups = [
+    ['challenge', args.challengeSelectors],
+    ['logged_out', args.loggedOutSelectors],
+    ['logged_in', args.loggedInSelectors]
+];
+if (/just a moment|attention required/i.test(document.title)) {
+    return { state: 'challenge', matched: 'title:' + document.title };
+}
+for (const [state, selectors] of groups) {
+    for (const selector of selectors) {
+        try {
+            if (document.querySelector(selector)) {
+                return { state, matched: selector };
+            }
+        } catch (error) {
+            console.warn('[4AI LOGIN] Bad selector:', selector, error.message);
+        }
+    }
+}
+return { state: 'unknown', matched: null };
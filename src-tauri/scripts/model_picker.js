@@ -0,0 +1,57 @@
+// @version 1.0.0
+// @description Reads the active model and optionally picks another one from the model menu
+// @param menuSelectors - Button that opens the model menu
+// @param optionSelectors - Entries of the opened menu
+// @param activeSelectors - Element showing the active model
+// @param labels - Option labels of the wanted model, best first
+// @param open - Open the menu and click the matching option
+// @param settleMs - Pause after opening the menu and after choosing
+
+const sleep = (ms) => new Promise((resolve) => setTimeout(resolve, ms));
+const normalize = (text) => (text || '').replace(/\s+/g, ' ').trim().toLowerCase();
+const query = (selector) => {
+    try { return Array.from(document.querySelectorAll(selector)); } catch (e) { return []; }
+};
+const firstVisible = (selectors) => {
+    for (const selector of selectors) {
+        const el = query(selector).find((candidate) => candidate.offsetParent !== null);
+        if (el) return el;
+    }
+    return null;
+};
+const activeText = () => {
+    const el = firstVisible(args.activeSelectors.length > 0 ? args.activeSelectors : args.menuSelectors);
+    return el ? (el.innerText || el.textContent || '').trim() : '';
+};
+
+if (!args.open) {
+    return { active: activeText(), option: null };
+}
+
+const menu = firstVisible(args.menuSelectors);
+if (!menu) {
+    throw new Error('Model menu not found: ' + args.menuSelectors.join(', '));
+}
+menu.click();
+await sleep(args.settleMs);
+
+// An option's first line is its name; exact names win over prefixes
+const options = args.optionSelectors.flatMap(query).filter((el) => el.offsetParent !== null);
+const nameOf = (el) => normalize((el.innerText || el.textContent || '').split('\n')[0]);
+let chosen = null;
+for (const label of args.labels.map(normalize)) {
+    chosen = options.find((el) => nameOf(el) === label)
+        || options.find((el) => nameOf(el).startsWith(label + ' ') || nameOf(el).endsWith(' ' + label));
+    if (chosen) break;
+}
+if (!chosen) {
+    // Close the menu again so the page is left as it was
+    document.body.dispatchEvent(new KeyboardEvent('keydown', { key: 'Escape', bubbles: true }));
+    await sleep(args.settleMs);
+    return { active: activeText(), option: null };
+}
+
+const option = (chosen.innerText || chosen.textContent || '').split('\n')[0].trim();
+chosen.click();
+await sleep(args.settleMs);
+return { active: activeText(), option };
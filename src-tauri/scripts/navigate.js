@@ -0,0 +1,6 @@
+// @version 1.0.0
+// @description Navigates the page to another URL
+// @param url - URL to load
+
+window.location.assign(args.url);
+return true;
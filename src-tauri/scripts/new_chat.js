@@ -0,0 +1,14 @@
+// @version 1.0.0
+// @description Clicks the service's "new chat" button
+// @param selectors - Candidate new chat button selectors, in order
+
+for (const selector of args.selectors) {
+    let elements = [];
+    try { elements = Array.from(document.querySelectorAll(selector)); } catch (e) { continue; }
+    const button = elements.find((el) => el.offsetParent !== null && !el.disabled);
+    if (button) {
+        button.click();
+        return selector;
+    }
+}
+return null;
@@ -0,0 +1,18 @@
+// @version 1.0.0
+// @description Moves a small extracted response into the title as base64
+
+const content = window.__4AI_CONTENT_RESULT || '';
+const safeContent = content.substring(0, 1500); // Safe limit for Rust return
+
+// Clear the stored content
+window.__4AI_CONTENT_RESULT = null;
+window.__4AI_CONTENT_CHUNKS = null;
+window.__4AI_CHUNK_COUNT = null;
+
+// Store in title for Rust to parse (limited length)
+if (safeContent.length < 500) {
+    document.title = '[4AI_CONTENT]' + btoa(safeContent) + '[/4AI_CONTENT]';
+}
+
+console.log('[4AI EXTRACT] Content prepared for Rust:', safeContent.length, 'chars');
+return safeContent.length;
@@ -0,0 +1,37 @@
+// @version 1.1.0
+// @description Returns the trimmed text of the first element matching a selector
+// @param selector - CSS selector
+// @param excludeSelectors - UI chrome removed from a copy of the element before reading it
+
+// Copy of the element without the service's UI chrome
+const clean = (el) => {
+    const copy = el.cloneNode(true);
+    for (const selector of args.excludeSelectors) {
+        try { copy.querySelectorAll(selector).forEach((node) => node.remove()); } catch (e) { /* invalid selector */ }
+    }
+    return copy;
+};
+
+try {
+    const found = document.querySelector(args.selector);
+    if (!found) {
+        console.log('[4AI] Element not found for selector:', args.selector);
+        return '';
+    }
+    const element = clean(found);
+
+    let text = element.textContent || element.innerText || '';
+    text = text.trim();
+
+    // For debugging
+    console.log('[4AI] Extracted text length:', text.length);
+    console.log('[4AI] First 100 chars:', text.substring(0, 100));
+
+    // Store result globally for retrieval
+    window.__4AI_EXTRACTED_TEXT = text;
+    return text;
+} catch (error) {
+    console.error('[4AI] Error extracting text:', error);
+    window.__4AI_EXTRACTED_TEXT = '';
+    return '';
+}
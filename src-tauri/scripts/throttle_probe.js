@@ -0,0 +1,13 @@
+// @version 1.0.0
+// @description Measures timer drift and animation frames to detect background throttling
+
+const started = performance.now();
+await new Promise((resolve) => setTimeout(resolve, 100));
+const drift = performance.now() - started - 100;
+let frames = 0;
+await new Promise((resolve) => {
+    const tick = () => { frames++; if (frames < 5) requestAnimationFrame(tick); };
+    requestAnimationFrame(tick);
+    setTimeout(resolve, 500);
+});
+return { visibilityState: document.visibilityState, drift, frames };
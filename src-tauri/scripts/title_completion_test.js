@@ -0,0 +1,7 @@
+// @version 1.0.0
+// @description Sets a fake completion title signal for test_title_communication
+
+console.log('[TITLE TEST] Setting completion signal...');
+document.title = '4AI_COMPLETE_RUST_TEST_test_9999_' + Date.now();
+console.log('[TITLE TEST] Completion signal set:', document.title);
+return 'COMPLETION_SIGNAL_SET';
@@ -0,0 +1,7 @@
+// @version 1.0.0
+// @description Sets a test title for test_title_communication
+
+console.log('[TITLE TEST] Before:', document.title);
+document.title = 'RUST_TITLE_TEST_' + Date.now();
+console.log('[TITLE TEST] After:', document.title);
+return document.title;
@@ -0,0 +1,349 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use tauri::command;
+use crate::cmd::runs::RunRegistry;
+use crate::utils::{log_success, log_warning, log_with_context};
+
+// Longer "names" are sentences, not file names
+const MAX_FILENAME_LENGTH: usize = 120;
+
+/// One fenced code block of an answer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CodeArtifact {
+    // "<step>-<n>", unique within the run
+    pub id: String,
+    pub language: Option<String>,
+    // File name the answer gives for the block
+    pub filename: Option<String>,
+    pub content: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SaveAction {
+    Create,
+    Overwrite,
+    // The file exists and overwriting was not allowed
+    Skip,
+    // The file already has this content
+    Unchanged,
+    // A later selected artifact writes the same path
+    Superseded,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ArtifactWrite {
+    pub id: String,
+    pub path: String,
+    pub exists: bool,
+    pub action: SaveAction,
+    pub bytes: usize,
+    // Why the artifact was not saved under the name the answer gave
+    pub warning: Option<String>,
+}
+
+fn looks_like_filename(token: &str) -> bool {
+    if token.is_empty() || token.len() > MAX_FILENAME_LENGTH || token.contains("://") {
+        return false;
+    }
+    if !token.chars().all(|c| c.is_alphanumeric() || "-_./\\".contains(c)) {
+        return false;
+    }
+    let name = token.rsplit(['/', '\\']).next().unwrap_or(token);
+    if matches!(name, "Dockerfile" | "Makefile" | "Procfile" | "Gemfile") {
+        return true;
+    }
+    // "1.5" or "e.g." are not file names
+    match name.rsplit_once('.') {
+        Some((_, extension)) => {
+            (1..=10).contains(&extension.len())
+                && extension.chars().all(|c| c.is_ascii_alphanumeric())
+                && extension.chars().any(|c| c.is_ascii_alphabetic())
+        }
+        None => false,
+    }
+}
+
+// "```rust:src/main.rs" or "```python title=app.py"
+fn filename_from_info(info: &str) -> Option<String> {
+    let mut words = info.split_whitespace();
+    let first = words.next()?;
+    if let Some((_, path)) = first.split_once(':') {
+        return Some(path.to_string()).filter(|path| looks_like_filename(path));
+    }
+    words
+        .filter_map(|word| word.split_once('='))
+        .filter(|(key, _)| matches!(*key, "title" | "file" | "filename"))
+        .map(|(_, value)| value.trim_matches(['"', '\'']).to_string())
+        .find(|value| looks_like_filename(value))
+}
+
+// "// src/main.rs", "# file: app.py", "<!-- index.html -->"
+fn filename_from_comment(first_line: &str) -> Option<String> {
+    let line = first_line.trim();
+    let body = ["//", "#", "--", "/*", "<!--", ";"]
+        .iter()
+        .find_map(|marker| line.strip_prefix(marker))?
+        .trim_end_matches("-->")
+        .trim_end_matches("*/")
+        .trim();
+    let lower = body.to_lowercase();
+    let body = ["filename:", "file:", "path:"]
+        .iter()
+        .find_map(|prefix| lower.starts_with(prefix).then(|| body[prefix.len()..].trim()))
+        .unwrap_or(body);
+    looks_like_filename(body).then(|| body.to_string())
+}
+
+// "Create `src/lib.rs`:" or "### main.py"
+fn filename_from_context(line: &str) -> Option<String> {
+    let quoted = line
+        .split(['`', '*'])
+        .map(str::trim)
+        .rfind(|token| looks_like_filename(token));
+    if let Some(name) = quoted {
+        return Some(name.to_string());
+    }
+    let bare = line.trim_start_matches('#').trim().trim_end_matches(':').trim();
+    looks_like_filename(bare).then(|| bare.to_string())
+}
+
+fn fence_of(line: &str) -> Option<(char, usize, &str)> {
+    let trimmed = line.trim_start();
+    let mark = trimmed.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let length = trimmed.chars().take_while(|c| *c == mark).count();
+    (length >= 3).then(|| (mark, length, trimmed[length..].trim()))
+}
+
+/// Fenced code blocks of a Markdown answer, with the file name the answer
+/// gives for each: in the fence info, a first-line comment, or the line above.
+pub fn extract_artifacts(markdown: &str, step: usize) -> Vec<CodeArtifact> {
+    let mut artifacts = Vec::new();
+    let mut context = "";
+    let mut lines = markdown.lines();
+    while let Some(line) = lines.next() {
+        let Some((mark, length, info)) = fence_of(line) else {
+            if !line.trim().is_empty() {
+                context = line;
+            }
+            continue;
+        };
+        // Blocks inside list items are indented with their item
+        let indent = line.len() - line.trim_start().len();
+        let mut content = Vec::new();
+        for line in lines.by_ref() {
+            if fence_of(line).is_some_and(|(m, l, rest)| m == mark && l >= length && rest.is_empty()) {
+                break;
+            }
+            let strip = line.len() - line.trim_start().len();
+            content.push(&line[strip.min(indent)..]);
+        }
+
+        let language = info.split([' ', ':']).next().filter(|language| !language.is_empty());
+        let filename = filename_from_info(info)
+            .or_else(|| content.first().and_then(|first| filename_from_comment(first)))
+            .or_else(|| filename_from_context(context));
+        artifacts.push(CodeArtifact {
+            id: format!("{}-{}", step, artifacts.len() + 1),
+            language: language.map(str::to_lowercase),
+            filename,
+            content: content.join("\n"),
+        });
+        context = "";
+    }
+    artifacts
+}
+
+fn extension_for(language: Option<&str>) -> &'static str {
+    match language.unwrap_or_default() {
+        "python" | "py" => "py",
+        "rust" | "rs" => "rs",
+        "javascript" | "js" => "js",
+        "typescript" | "ts" => "ts",
+        "tsx" => "tsx",
+        "jsx" => "jsx",
+        "json" => "json",
+        "html" => "html",
+        "css" => "css",
+        "bash" | "sh" | "shell" | "zsh" => "sh",
+        "powershell" | "ps1" => "ps1",
+        "go" => "go",
+        "java" => "java",
+        "kotlin" => "kt",
+        "swift" => "swift",
+        "c" => "c",
+        "cpp" | "c++" => "cpp",
+        "csharp" | "cs" => "cs",
+        "ruby" => "rb",
+        "php" => "php",
+        "sql" => "sql",
+        "yaml" | "yml" => "yaml",
+        "toml" => "toml",
+        "xml" => "xml",
+        "markdown" | "md" => "md",
+        _ => "txt",
+    }
+}
+
+fn default_path(artifact: &CodeArtifact) -> PathBuf {
+    PathBuf::from(format!("artifact-{}.{}", artifact.id, extension_for(artifact.language.as_deref())))
+}
+
+/// Where the artifact goes below the target directory. Names from answers
+/// must stay inside it.
+pub fn relative_path(artifact: &CodeArtifact) -> Result<PathBuf, String> {
+    let Some(name) = artifact.filename.as_deref() else {
+        return Ok(default_path(artifact));
+    };
+    let path = PathBuf::from(name.replace('\\', "/"));
+    if path.components().any(|part| !matches!(part, Component::Normal(_) | Component::CurDir)) {
+        return Err(format!("Artifact {} names a path outside the target directory: {}", artifact.id, name));
+    }
+    Ok(path)
+}
+
+/// What saving `artifacts` into `directory` would do, without writing anything.
+/// An artifact whose name would leave the directory falls back to its default
+/// name and carries a warning instead of failing the whole save.
+pub fn plan_writes(artifacts: &[CodeArtifact], directory: &Path, overwrite: bool) -> Vec<ArtifactWrite> {
+    let mut plan: Vec<ArtifactWrite> = Vec::new();
+    let mut by_path: HashMap<PathBuf, usize> = HashMap::new();
+    for artifact in artifacts {
+        let (relative, warning) = match relative_path(artifact) {
+            Ok(relative) => (relative, None),
+            Err(e) => {
+                log_warning("ARTIFACTS", &e);
+                (default_path(artifact), Some(e))
+            }
+        };
+        let path = directory.join(&relative);
+        let existing = std::fs::read(&path).ok();
+        let action = match existing {
+            None => SaveAction::Create,
+            Some(bytes) if bytes == artifact.content.as_bytes() => SaveAction::Unchanged,
+            Some(_) if overwrite => SaveAction::Overwrite,
+            Some(_) => SaveAction::Skip,
+        };
+        if let Some(previous) = by_path.insert(relative, plan.len()) {
+            plan[previous].action = SaveAction::Superseded;
+        }
+        plan.push(ArtifactWrite {
+            id: artifact.id.clone(),
+            path: path.to_string_lossy().to_string(),
+            exists: path.exists(),
+            action,
+            bytes: artifact.content.len(),
+            warning,
+        });
+    }
+    plan
+}
+
+/// Writes the selected artifacts of a run (all when `ids` is empty) into
+/// `directory`. With `preview`, only reports what would be created,
+/// overwritten or skipped.
+#[command]
+pub async fn save_artifacts(
+    runs: tauri::State<'_, RunRegistry>,
+    run_id: String,
+    directory: String,
+    ids: Option<Vec<String>>,
+    overwrite: Option<bool>,
+    preview: Option<bool>,
+) -> Result<Vec<ArtifactWrite>, String> {
+    let run = runs.get(&run_id).ok_or_else(|| format!("Run '{}' not found", run_id))?;
+    let ids = ids.unwrap_or_default();
+    let artifacts: Vec<CodeArtifact> = run
+        .steps
+        .iter()
+        .flat_map(|step| step.artifacts.iter().cloned())
+        .filter(|artifact| ids.is_empty() || ids.contains(&artifact.id))
+        .collect();
+    if artifacts.is_empty() {
+        return Err(format!("No matching artifacts in run '{}'", run_id));
+    }
+
+    let directory = PathBuf::from(directory);
+    let plan = plan_writes(&artifacts, &directory, overwrite.unwrap_or(false));
+    if preview.unwrap_or(false) {
+        return Ok(plan);
+    }
+
+    log_with_context("ARTIFACTS", &format!("{}: saving {} artifact(s) to {}", run_id, plan.len(), directory.display()));
+    for (artifact, write) in artifacts.iter().zip(&plan) {
+        if !matches!(write.action, SaveAction::Create | SaveAction::Overwrite) {
+            continue;
+        }
+        let path = Path::new(&write.path);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        std::fs::write(path, &artifact.content).map_err(|e| format!("Failed to write {}: {}", write.path, e))?;
+    }
+    let written = plan
+        .iter()
+        .filter(|write| matches!(write.action, SaveAction::Create | SaveAction::Overwrite))
+        .count();
+    log_success("ARTIFACTS", &format!("{}: wrote {} file(s)", run_id, written));
+    Ok(plan)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_artifacts_with_inferred_filenames() {
+        let answer = "Create `src/lib.rs`:\n\n```rust\npub fn a() {}\n```\n\n\
+                      ```python\n# file: tools/run.py\nprint(1)\n```\n\n\
+                      ```toml title=\"Cargo.toml\"\n[package]\n```\n\n\
+                      Version 1.5 works:\n\n```\nplain\n```";
+        let artifacts = extract_artifacts(answer, 2);
+        let names: Vec<_> = artifacts.iter().map(|a| a.filename.as_deref()).collect();
+        assert_eq!(names, vec![Some("src/lib.rs"), Some("tools/run.py"), Some("Cargo.toml"), None]);
+        assert_eq!(artifacts[0].language.as_deref(), Some("rust"));
+        assert_eq!(artifacts[0].content, "pub fn a() {}");
+        assert_eq!(artifacts[3].id, "2-4");
+        assert_eq!(artifacts[3].language, None);
+    }
+
+    #[test]
+    fn test_indented_blocks_and_default_names() {
+        let answer = "1. Install:\n   ```bash\n   npm install\n   ```";
+        let artifacts = extract_artifacts(answer, 1);
+        assert_eq!(artifacts[0].content, "npm install");
+        assert_eq!(relative_path(&artifacts[0]).unwrap(), PathBuf::from("artifact-1-1.sh"));
+
+        let escaping = CodeArtifact { filename: Some("../../.bashrc".to_string()), ..artifacts[0].clone() };
+        assert!(relative_path(&escaping).is_err());
+    }
+
+    #[test]
+    fn test_plan_reports_overwrites() {
+        let dir = std::env::temp_dir().join(format!("artifacts-plan-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a.py"), "old").unwrap();
+        let artifact = |id: &str, name: &str, content: &str| CodeArtifact {
+            id: id.to_string(),
+            language: Some("python".to_string()),
+            filename: Some(name.to_string()),
+            content: content.to_string(),
+        };
+        let artifacts = vec![artifact("1-1", "a.py", "new"), artifact("1-2", "b.py", "x"), artifact("2-1", "b.py", "y")];
+
+        let actions = |overwrite| -> Vec<SaveAction> {
+            plan_writes(&artifacts, &dir, overwrite).iter().map(|w| w.action).collect()
+        };
+        assert_eq!(actions(false), vec![SaveAction::Skip, SaveAction::Superseded, SaveAction::Create]);
+        assert_eq!(actions(true)[0], SaveAction::Overwrite);
+
+        let escaping = vec![artifact("3-1", "../x.py", "z"), artifact("3-2", "c.py", "w")];
+        let plan = plan_writes(&escaping, &dir, false);
+        assert_eq!(plan[0].path, dir.join("artifact-3-1.py").to_string_lossy());
+        assert!(plan[0].warning.is_some());
+        assert_eq!(plan[1].action, SaveAction::Create);
+        assert!(plan[1].warning.is_none());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,284 @@
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::path::Path;
+use std::time::Duration;
+use tauri::Manager;
+use crate::cmd::config::AttachmentSelectors;
+use crate::cmd::runs::RunRegistry;
+use crate::cmd::scripts::run_script;
+use crate::utils::{log_with_context, log_success, log_warning};
+
+const UPLOAD_SCRIPT_TIMEOUT_MS: u64 = 30000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AttachmentMethod {
+    // Set on the service's <input type="file">
+    FileInput,
+    // Dropped onto the editor when no file input was found
+    Drop,
+    // Plain-text file pasted into the prompt
+    PastedText,
+}
+
+/// What is known about a file before it is attached anywhere.
+#[derive(Debug, Clone, Serialize)]
+pub struct FileInfo {
+    pub path: String,
+    pub name: String,
+    pub extension: String,
+    pub size: u64,
+    pub mime_type: String,
+}
+
+/// One file as received by one service; stored on the run per service.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttachmentRecord {
+    pub name: String,
+    pub size: u64,
+    pub mime_type: String,
+    pub method: AttachmentMethod,
+    // File input or drop target that took the file
+    pub selector: Option<String>,
+}
+
+/// Files ready to go into a service: uploads plus text spliced into the prompt.
+#[derive(Debug, Default)]
+pub struct PreparedAttachments {
+    pub uploads: Vec<(FileInfo, Vec<u8>)>,
+    pub texts: Vec<(FileInfo, String)>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UploadResult {
+    method: AttachmentMethod,
+    selector: String,
+}
+
+pub fn mime_type_for(extension: &str) -> &'static str {
+    match extension {
+        "pdf" => "application/pdf",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "docx" => "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+        "xlsx" => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        "pptx" => "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+        "zip" => "application/zip",
+        "mp3" => "audio/mpeg",
+        "mp4" => "video/mp4",
+        "json" => "application/json",
+        "csv" => "text/csv",
+        "md" | "markdown" => "text/markdown",
+        "html" => "text/html",
+        "xml" => "application/xml",
+        "txt" | "log" => "text/plain",
+        "js" | "jsx" | "ts" | "tsx" | "py" | "rs" | "go" | "java" | "c" | "h" | "cpp" | "cs" | "sh" | "sql"
+        | "yaml" | "yml" | "toml" | "css" | "tsv" => "text/plain",
+        _ => "application/octet-stream",
+    }
+}
+
+pub fn file_info(path: &str) -> Result<FileInfo, String> {
+    let metadata = std::fs::metadata(path).map_err(|e| format!("Cannot read {}: {}", path, e))?;
+    if !metadata.is_file() {
+        return Err(format!("{} is not a file", path));
+    }
+    let file = Path::new(path);
+    let extension = file
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    Ok(FileInfo {
+        path: path.to_string(),
+        name: file.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default(),
+        mime_type: mime_type_for(&extension).to_string(),
+        extension,
+        size: metadata.len(),
+    })
+}
+
+/// Checks the service's count, type and size limits; `Ok(true)` means the
+/// file is pasted as text rather than uploaded.
+pub fn check_limits(file: &FileInfo, limits: &AttachmentSelectors) -> Result<bool, String> {
+    let as_text = limits.paste_as_text_extensions.contains(&file.extension);
+    if !as_text
+        && !limits.allowed_extensions.is_empty()
+        && !limits.allowed_extensions.contains(&file.extension)
+    {
+        return Err(format!("{}: .{} files are not accepted", file.name, file.extension));
+    }
+    if file.size > limits.max_file_size_mb * 1024 * 1024 {
+        return Err(format!(
+            "{}: {} bytes exceeds the {} MB limit",
+            file.name, file.size, limits.max_file_size_mb
+        ));
+    }
+    Ok(as_text)
+}
+
+/// Reads and validates every file up front so nothing is attached when one is rejected.
+pub fn prepare(paths: &[String], limits: &AttachmentSelectors) -> Result<PreparedAttachments, String> {
+    if paths.len() > limits.max_files {
+        return Err(format!("{} files given, at most {} can be attached", paths.len(), limits.max_files));
+    }
+    let mut prepared = PreparedAttachments::default();
+    for path in paths {
+        let info = file_info(path)?;
+        if check_limits(&info, limits)? {
+            let text = std::fs::read_to_string(path)
+                .map_err(|e| format!("{} is not readable as text: {}", info.name, e))?;
+            prepared.texts.push((info, text));
+        } else {
+            let bytes = std::fs::read(path).map_err(|e| format!("Cannot read {}: {}", info.name, e))?;
+            prepared.uploads.push((info, bytes));
+        }
+    }
+    Ok(prepared)
+}
+
+/// The prompt with each text attachment pasted above it as a fenced block.
+pub fn prompt_with_texts(prompt: &str, texts: &[(FileInfo, String)]) -> String {
+    if texts.is_empty() {
+        return prompt.to_string();
+    }
+    let mut out = String::new();
+    for (info, text) in texts {
+        // A fence longer than any backtick run inside the file
+        let longest = text
+            .split(|c| c != '`')
+            .map(str::len)
+            .max()
+            .unwrap_or(0);
+        let fence = "`".repeat(longest.max(2) + 1);
+        out.push_str(&format!("{}:\n{}{}\n{}\n{}\n\n", info.name, fence, info.extension, text.trim_end(), fence));
+    }
+    out.push_str(prompt);
+    out
+}
+
+/// Puts the uploads into the service page, returning what each file went through.
+pub async fn upload(
+    window: &tauri::Window,
+    uploads: &[(FileInfo, Vec<u8>)],
+    limits: &AttachmentSelectors,
+) -> Result<Vec<AttachmentRecord>, String> {
+    if uploads.is_empty() {
+        return Ok(Vec::new());
+    }
+    let files: Vec<_> = uploads
+        .iter()
+        .map(|(info, bytes)| {
+            json!({
+                "name": info.name,
+                "mimeType": info.mime_type,
+                "base64": base64::engine::general_purpose::STANDARD.encode(bytes),
+            })
+        })
+        .collect();
+    let args = json!({
+        "files": files,
+        "fileInputSelectors": limits.file_input_selectors,
+        "dropTargetSelectors": limits.drop_target_selectors,
+        "settleMs": limits.upload_settle_ms,
+    });
+    let timeout = Duration::from_millis(UPLOAD_SCRIPT_TIMEOUT_MS + limits.upload_settle_ms);
+    let value = run_script(window, "attach_files", &args, timeout).await?;
+    let result: UploadResult =
+        serde_json::from_value(value).map_err(|e| format!("Invalid upload result: {}", e))?;
+
+    Ok(uploads
+        .iter()
+        .map(|(info, _)| AttachmentRecord {
+            name: info.name.clone(),
+            size: info.size,
+            mime_type: info.mime_type.clone(),
+            method: result.method,
+            selector: Some(result.selector.clone()),
+        })
+        .collect())
+}
+
+pub fn text_records(texts: &[(FileInfo, String)]) -> Vec<AttachmentRecord> {
+    texts
+        .iter()
+        .map(|(info, _)| AttachmentRecord {
+            name: info.name.clone(),
+            size: info.size,
+            mime_type: info.mime_type.clone(),
+            method: AttachmentMethod::PastedText,
+            selector: None,
+        })
+        .collect()
+}
+
+/// Stores what `service_id` received under the run, next to its profile.
+pub fn record_on_run(app: &tauri::AppHandle, run_id: &str, service_id: &str, records: &[AttachmentRecord]) {
+    if records.is_empty() {
+        return;
+    }
+    let records = records.to_vec();
+    match app.state::<RunRegistry>().update(run_id, |run| {
+        run.attachments.entry(service_id.to_string()).or_default().extend(records)
+    }) {
+        Ok(_) => log_success("ATTACH", &format!("Recorded attachments of {} on {}", service_id, run_id)),
+        Err(e) => log_warning("ATTACH", &format!("Could not record attachments on {}: {}", run_id, e)),
+    }
+}
+
+pub fn log_prepared(label: &str, prepared: &PreparedAttachments) {
+    log_with_context(
+        "ATTACH",
+        &format!(
+            "{}: {} file(s) to upload, {} pasted as text",
+            label,
+            prepared.uploads.len(),
+            prepared.texts.len()
+        ),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(name: &str, size: u64) -> FileInfo {
+        let extension = name.rsplit('.').next().unwrap().to_string();
+        FileInfo {
+            path: name.to_string(),
+            name: name.to_string(),
+            mime_type: mime_type_for(&extension).to_string(),
+            extension,
+            size,
+        }
+    }
+
+    fn limits() -> AttachmentSelectors {
+        AttachmentSelectors {
+            max_file_size_mb: 1,
+            allowed_extensions: vec!["pdf".to_string()],
+            paste_as_text_extensions: vec!["md".to_string()],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_limits_split_uploads_and_text() {
+        assert_eq!(check_limits(&info("a.pdf", 10), &limits()), Ok(false));
+        assert_eq!(check_limits(&info("notes.md", 10), &limits()), Ok(true));
+        assert!(check_limits(&info("a.exe", 10), &limits()).is_err());
+        assert!(check_limits(&info("big.pdf", 2 * 1024 * 1024), &limits()).is_err());
+    }
+
+    #[test]
+    fn test_text_is_fenced_above_the_prompt() {
+        let texts = vec![(info("notes.md", 0), "```rust\nfn main() {}\n```\n".to_string())];
+        let prompt = prompt_with_texts("Summarize", &texts);
+        assert!(prompt.starts_with("notes.md:\n````md\n```rust"));
+        assert!(prompt.ends_with("```\n````\n\nSummarize"));
+    }
+}
@@ -0,0 +1,223 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::time::Duration;
+use tauri::{command, Manager, Runtime, WindowBuilder};
+use crate::cmd::config::service_selectors;
+use crate::cmd::runs::{RunMetadata, RunRegistry};
+use crate::cmd::scripts::run_script;
+use crate::cmd::sessions::WebviewSessions;
+use crate::utils::{log_with_context, log_success, log_warning};
+
+pub const BACKGROUND_THROTTLED_EVENT: &str = "service://background-throttled";
+
+// Far enough to be off every monitor layout we have seen
+const OFFSCREEN_POSITION: f64 = -10000.0;
+// setTimeout(100) taking longer than this means the page is being throttled
+const TIMER_DRIFT_LIMIT_MS: f64 = 400.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WindowMode {
+    #[default]
+    Visible,
+    Hidden,
+    Minimized,
+    // Shown (so the page keeps rendering at full speed) but placed off-screen
+    Offscreen,
+}
+
+impl WindowMode {
+    pub fn is_background(self) -> bool {
+        self != WindowMode::Visible
+    }
+}
+
+/// Mode for one service window: explicit request, then the run's per-service
+/// override, then the service's own background preference, then the run default.
+pub fn resolve_window_mode(
+    explicit: Option<WindowMode>,
+    run_mode: WindowMode,
+    run_override: Option<WindowMode>,
+    service_preference: Option<WindowMode>,
+) -> WindowMode {
+    if let Some(mode) = explicit.or(run_override) {
+        return mode;
+    }
+    match (run_mode.is_background(), service_preference) {
+        (true, Some(preferred)) => preferred,
+        _ => run_mode,
+    }
+}
+
+/// `resolve_window_mode` for a service window of `run`, with the service's
+/// preference read from its config.
+pub fn window_mode_for(explicit: Option<WindowMode>, run: Option<&RunMetadata>, service_id: Option<&str>) -> WindowMode {
+    resolve_window_mode(
+        explicit,
+        run.map(|r| r.window_mode).unwrap_or_default(),
+        run.zip(service_id).and_then(|(r, id)| r.service_window_modes.get(id).copied()),
+        service_id
+            .and_then(|id| service_selectors(id).ok())
+            .and_then(|config| config.background_mode),
+    )
+}
+
+/// Minimized windows are built hidden and only shown by `apply_to_window`
+/// once minimized, so they never flash on screen or take focus.
+pub fn apply_to_builder<'a, R: Runtime>(builder: WindowBuilder<'a, R>, mode: WindowMode) -> WindowBuilder<'a, R> {
+    match mode {
+        WindowMode::Visible => builder.center().visible(true),
+        WindowMode::Hidden => builder.visible(false).focused(false).skip_taskbar(true),
+        WindowMode::Minimized => builder.center().visible(false).focused(false),
+        WindowMode::Offscreen => builder
+            .position(OFFSCREEN_POSITION, OFFSCREEN_POSITION)
+            .visible(true)
+            .focused(false)
+            .skip_taskbar(true),
+    }
+}
+
+/// Puts an existing window (back) into a mode.
+pub fn apply_to_window<R: Runtime>(window: &tauri::Window<R>, mode: WindowMode) {
+    let result = match mode {
+        WindowMode::Visible => window
+            .set_skip_taskbar(false)
+            .and_then(|_| window.show())
+            .and_then(|_| window.unminimize())
+            .and_then(|_| window.center()),
+        WindowMode::Hidden => window.hide(),
+        WindowMode::Minimized => window.minimize().and_then(|_| window.show()),
+        WindowMode::Offscreen => window
+            .set_skip_taskbar(true)
+            .and_then(|_| window.set_position(tauri::Position::Logical(tauri::LogicalPosition {
+                x: OFFSCREEN_POSITION,
+                y: OFFSCREEN_POSITION,
+            }))),
+    };
+    if let Err(e) = result {
+        log_warning("BACKGROUND", &format!("Failed to apply {:?} to {}: {}", mode, window.label(), e));
+    }
+}
+
+/// Makes a background window usable by a human: on screen, restored and focused.
+pub fn reveal(app: &tauri::AppHandle, label: &str) {
+    if let Some(window) = app.get_window(label) {
+        let _ = window.set_skip_taskbar(false);
+        let _ = window.show();
+        let _ = window.unminimize();
+        let _ = window.center();
+        let _ = window.set_focus();
+    }
+}
+
+/// Returns a revealed window to the mode it was created with.
+pub fn conceal(app: &tauri::AppHandle, label: &str) {
+    let mode = app.state::<WebviewSessions>().get(label).map(|s| s.window_mode);
+    if let (Some(window), Some(mode)) = (app.get_window(label), mode) {
+        if mode.is_background() {
+            apply_to_window(&window, mode);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BackgroundHealth {
+    pub label: String,
+    pub service_id: Option<String>,
+    pub window_mode: WindowMode,
+    pub visibility_state: String,
+    pub timer_drift_ms: f64,
+    pub animation_frames: u64,
+    pub throttled: bool,
+}
+
+/// Measures timer drift and animation frames to spot pages that stall in the background.
+pub async fn probe_background_health(app: &tauri::AppHandle, label: &str) -> Result<BackgroundHealth, String> {
+    let window = app
+        .get_window(label)
+        .ok_or_else(|| "Window not found".to_string())?;
+    let session = app.state::<WebviewSessions>().get(label);
+    let window_mode = session.as_ref().map(|s| s.window_mode).unwrap_or_default();
+
+    let probe = run_script(&window, "throttle_probe", &json!({}), Duration::from_secs(10)).await?;
+    let timer_drift_ms = probe["drift"].as_f64().unwrap_or(0.0);
+    let animation_frames = probe["frames"].as_u64().unwrap_or(0);
+
+    Ok(BackgroundHealth {
+        label: label.to_string(),
+        service_id: session.as_ref().and_then(|s| s.service_id.clone()),
+        window_mode,
+        visibility_state: probe["visibilityState"].as_str().unwrap_or("unknown").to_string(),
+        timer_drift_ms,
+        animation_frames,
+        throttled: timer_drift_ms > TIMER_DRIFT_LIMIT_MS || animation_frames == 0,
+    })
+}
+
+/// Probes a background window and, if it is throttled, logs it, records it on
+/// the owning run and emits `service://background-throttled`.
+pub async fn report_background_health(app: &tauri::AppHandle, label: &str) -> Result<BackgroundHealth, String> {
+    let health = probe_background_health(app, label).await?;
+    if !health.throttled {
+        log_success("BACKGROUND", &format!("{} runs fine in {:?} mode", label, health.window_mode));
+        return Ok(health);
+    }
+
+    let message = format!(
+        "{} ({}) is throttled in {:?} mode: drift {:.0} ms, {} frames, visibility {}",
+        label,
+        health.service_id.as_deref().unwrap_or("unknown service"),
+        health.window_mode,
+        health.timer_drift_ms,
+        health.animation_frames,
+        health.visibility_state
+    );
+    log_warning("BACKGROUND", &message);
+
+    let run_id = app.state::<WebviewSessions>().get(label).and_then(|s| s.run_id);
+    if let Some(run_id) = run_id {
+        let _ = app.state::<RunRegistry>().update(&run_id, |run| run.warnings.push(message.clone()));
+    }
+    let _ = app.emit_all(BACKGROUND_THROTTLED_EVENT, health.clone());
+    Ok(health)
+}
+
+#[command]
+pub async fn check_background_health(app: tauri::AppHandle, label: String) -> Result<BackgroundHealth, String> {
+    log_with_context("BACKGROUND", &format!("Checking background health of {}", label));
+    report_background_health(&app, &label).await
+}
+
+#[command]
+pub async fn reveal_webview(app: tauri::AppHandle, label: String) -> Result<String, String> {
+    app.get_window(&label).ok_or_else(|| "Window not found".to_string())?;
+    reveal(&app, &label);
+    Ok(format!("Window '{}' revealed", label))
+}
+
+#[command]
+pub async fn conceal_webview(app: tauri::AppHandle, label: String) -> Result<String, String> {
+    app.get_window(&label).ok_or_else(|| "Window not found".to_string())?;
+    conceal(&app, &label);
+    Ok(format!("Window '{}' returned to background", label))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use WindowMode::*;
+
+    #[test]
+    fn test_explicit_and_per_service_modes_win() {
+        assert_eq!(resolve_window_mode(Some(Visible), Hidden, Some(Offscreen), Some(Minimized)), Visible);
+        assert_eq!(resolve_window_mode(None, Hidden, Some(Offscreen), Some(Minimized)), Offscreen);
+        assert_eq!(resolve_window_mode(None, Visible, Some(Hidden), None), Hidden);
+    }
+
+    #[test]
+    fn test_service_preference_only_applies_to_background_runs() {
+        assert_eq!(resolve_window_mode(None, Hidden, None, Some(Offscreen)), Offscreen);
+        assert_eq!(resolve_window_mode(None, Visible, None, Some(Offscreen)), Visible);
+        assert_eq!(resolve_window_mode(None, Minimized, None, None), Minimized);
+    }
+}
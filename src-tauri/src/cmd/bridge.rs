@@ -0,0 +1,100 @@
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{command, Manager};
+use tokio::sync::oneshot;
+
+// A script waiting for its result, and the window it was sent to
+struct PendingEval {
+    label: String,
+    reply: oneshot::Sender<Result<Value, String>>,
+}
+
+// `Window::eval` in Tauri 1.x cannot return a value, so scripts that need to
+// answer call back into `webview_eval_result` with the request id they were given.
+#[derive(Default)]
+pub struct EvalBridge {
+    next_id: AtomicU64,
+    pending: Mutex<HashMap<u64, PendingEval>>,
+}
+
+impl EvalBridge {
+    fn register(&self, label: &str) -> (u64, oneshot::Receiver<Result<Value, String>>) {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let (tx, rx) = oneshot::channel();
+        self.pending.lock().unwrap().insert(id, PendingEval { label: label.to_string(), reply: tx });
+        (id, rx)
+    }
+
+    fn cancel(&self, id: u64) {
+        self.pending.lock().unwrap().remove(&id);
+    }
+
+    // Only the window a request was sent to can answer it
+    fn resolve(&self, label: &str, id: u64, result: Result<Value, String>) -> bool {
+        let mut pending = self.pending.lock().unwrap();
+        if pending.get(&id).is_none_or(|eval| eval.label != label) {
+            return false;
+        }
+        match pending.remove(&id) {
+            Some(eval) => eval.reply.send(result).is_ok(),
+            None => false,
+        }
+    }
+}
+
+/// Runs `body` (the inside of an async JS function) in the window and waits
+/// for its return value. Fails on timeout, e.g. when the page has no IPC access.
+pub async fn eval_with_result(
+    window: &tauri::Window,
+    body: &str,
+    timeout: Duration,
+) -> Result<Value, String> {
+    let bridge = window.state::<EvalBridge>();
+    let (id, rx) = bridge.register(window.label());
+
+    let script = format!(
+        r#"
+        (function() {{
+            const report = (payload) => window.__TAURI_INVOKE__('webview_eval_result', Object.assign({{ requestId: {id} }}, payload));
+            Promise.resolve()
+                .then(async () => {{ {body} }})
+                .then((value) => report({{ value: value === undefined ? null : value, error: null }}))
+                .catch((error) => report({{ value: null, error: String((error && error.message) || error) }}));
+        }})();
+        "#,
+        id = id,
+        body = body
+    );
+
+    if let Err(e) = window.eval(&script) {
+        bridge.cancel(id);
+        return Err(format!("Script execution failed: {}", e));
+    }
+
+    match tokio::time::timeout(timeout, rx).await {
+        Ok(Ok(result)) => result,
+        Ok(Err(_)) => Err("Eval bridge dropped the request".to_string()),
+        Err(_) => {
+            bridge.cancel(id);
+            Err(format!("No result from '{}' within {} ms", window.label(), timeout.as_millis()))
+        }
+    }
+}
+
+#[command]
+pub fn webview_eval_result(
+    window: tauri::Window,
+    bridge: tauri::State<'_, EvalBridge>,
+    request_id: u64,
+    value: Option<Value>,
+    error: Option<String>,
+) {
+    let result = match error {
+        Some(error) => Err(error),
+        None => Ok(value.unwrap_or(Value::Null)),
+    };
+    bridge.resolve(window.label(), request_id, result);
+}
@@ -0,0 +1,172 @@
+use kuchikiki::traits::TendrilSink;
+use kuchikiki::NodeRef;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use crate::cmd::markdown::document_to_markdown;
+use crate::cmd::sources::{document_markdown_with_sources, Source};
+use crate::utils::log_warning;
+
+/// Per-service rules that keep UI chrome ("Copy code", feedback prompts,
+/// model labels) out of extracted answers.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CleanupRules {
+    // Removed from a clone of the response before its HTML or text is read
+    #[serde(default)]
+    pub exclude_selectors: Vec<String>,
+    // Regexes matched against each line of the extracted Markdown or text;
+    // matches are removed, and lines left empty are dropped
+    #[serde(default)]
+    pub text_filters: Vec<String>,
+}
+
+impl CleanupRules {
+    /// Detaches every element matching an exclude selector.
+    pub fn strip(&self, document: &NodeRef) {
+        for selector in &self.exclude_selectors {
+            let Ok(matches) = document.select(selector) else {
+                log_warning("CLEANUP", &format!("Invalid exclude selector '{}'", selector));
+                continue;
+            };
+            let nodes: Vec<NodeRef> = matches.map(|element| element.as_node().clone()).collect();
+            for node in nodes {
+                node.detach();
+            }
+        }
+    }
+
+    /// Applies the text filters line by line. Lines of fenced code are kept
+    /// as they are: "Edit" in a code block is not a button label.
+    pub fn filter(&self, text: &str) -> String {
+        let filters: Vec<Regex> = self
+            .text_filters
+            .iter()
+            .filter_map(|pattern| match Regex::new(pattern) {
+                Ok(filter) => Some(filter),
+                Err(e) => {
+                    log_warning("CLEANUP", &format!("Invalid text filter '{}': {}", pattern, e));
+                    None
+                }
+            })
+            .collect();
+        if filters.is_empty() {
+            return text.to_string();
+        }
+
+        let mut lines: Vec<String> = Vec::new();
+        let mut dropped = false;
+        // Backtick count of the open code fence
+        let mut fence: Option<usize> = None;
+        for line in text.lines() {
+            let ticks = line.trim_start().chars().take_while(|c| *c == '`').count();
+            match fence {
+                Some(open) => {
+                    if ticks >= open && line.trim().chars().all(|c| c == '`') {
+                        fence = None;
+                    }
+                    lines.push(line.to_string());
+                    continue;
+                }
+                None if ticks >= 3 => {
+                    fence = Some(ticks);
+                    dropped = false;
+                    lines.push(line.to_string());
+                    continue;
+                }
+                None => {}
+            }
+            let cleaned = filters
+                .iter()
+                .fold(line.to_string(), |line, filter| filter.replace_all(&line, "").into_owned());
+            if cleaned.trim().is_empty() && !line.trim().is_empty() {
+                dropped = true;
+                continue;
+            }
+            // A dropped line leaves no extra paragraph break behind
+            let blank = cleaned.trim().is_empty();
+            let after_break = lines.last().is_none_or(|last| last.trim().is_empty());
+            if std::mem::take(&mut dropped) && blank && after_break {
+                continue;
+            }
+            lines.push(if cleaned == line { cleaned } else { cleaned.trim_end().to_string() });
+        }
+        while lines.last().is_some_and(|last| last.trim().is_empty()) {
+            lines.pop();
+        }
+        lines.join("\n")
+    }
+
+    /// The response parsed, chrome removed.
+    pub fn document(&self, html: &str) -> NodeRef {
+        let document = kuchikiki::parse_html().one(html);
+        self.strip(&document);
+        document
+    }
+
+    /// Markdown of a response with sources as footnotes, chrome removed.
+    pub fn markdown_with_sources(&self, html: &str) -> (String, Vec<Source>) {
+        let (markdown, sources) = document_markdown_with_sources(&self.document(html));
+        (self.filter(&markdown), sources)
+    }
+
+    /// Markdown of a response with links left inline, chrome removed.
+    pub fn markdown(&self, html: &str) -> String {
+        self.filter(&document_to_markdown(&self.document(html)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Saved responses of each service, with the Markdown they should yield
+    const FIXTURES: &[(&str, &str, &str)] = &[
+        (
+            "chatgpt",
+            include_str!("../../tests/fixtures/cleanup/chatgpt.html"),
+            include_str!("../../tests/fixtures/cleanup/chatgpt.md"),
+        ),
+        (
+            "claude",
+            include_str!("../../tests/fixtures/cleanup/claude.html"),
+            include_str!("../../tests/fixtures/cleanup/claude.md"),
+        ),
+        (
+            "gemini",
+            include_str!("../../tests/fixtures/cleanup/gemini.html"),
+            include_str!("../../tests/fixtures/cleanup/gemini.md"),
+        ),
+        (
+            "copilot",
+            include_str!("../../tests/fixtures/cleanup/copilot.html"),
+            include_str!("../../tests/fixtures/cleanup/copilot.md"),
+        ),
+    ];
+
+    fn bundled_rules(service_id: &str) -> CleanupRules {
+        let config: serde_json::Value =
+            serde_json::from_str(include_str!("../../resources/webai-selectors.json")).unwrap();
+        serde_json::from_value(config["services"][service_id]["cleanup"].clone()).unwrap()
+    }
+
+    #[test]
+    fn test_fixtures_lose_their_chrome() {
+        for (service_id, html, expected) in FIXTURES {
+            let (markdown, _) = bundled_rules(service_id).markdown_with_sources(html);
+            assert_eq!(markdown, expected.trim_end(), "{}", service_id);
+        }
+    }
+
+    #[test]
+    fn test_filters_drop_emptied_lines_only() {
+        let rules = CleanupRules {
+            exclude_selectors: Vec::new(),
+            text_filters: vec!["^Copy code$".to_string(), r"\s*\(edited\)$".to_string(), "[".to_string()],
+        };
+        let text = "Intro (edited)\n\nCopy code\n\nfn main() {}\n\n\nEnd";
+        assert_eq!(rules.filter(text), "Intro\n\nfn main() {}\n\n\nEnd");
+
+        let code = "````\nCopy code\n```\nCopy code\n````\nCopy code";
+        assert_eq!(rules.filter(code), "````\nCopy code\n```\nCopy code\n````");
+    }
+}
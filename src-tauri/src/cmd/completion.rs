@@ -0,0 +1,412 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fmt;
+use std::time::{Duration, Instant};
+use tauri::{command, Manager};
+use crate::cmd::config::{service_selectors, ServiceSelectors};
+use crate::cmd::runs::RunRegistry;
+use crate::cmd::scripts::run_script;
+use crate::cmd::sessions::WebviewSessions;
+use crate::utils::{log_success, log_warning};
+
+const PROBE_TIMEOUT_MS: u64 = 3000;
+
+/// How much each signal counts towards `threshold`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompletionWeights {
+    #[serde(default = "default_weight")]
+    pub text_stable: f64,
+    #[serde(default = "default_weight")]
+    pub streaming_ended: f64,
+    #[serde(default = "default_weight")]
+    pub send_enabled: f64,
+    #[serde(default = "default_weight")]
+    pub stop_gone: f64,
+}
+
+fn default_weight() -> f64 {
+    0.5
+}
+
+impl Default for CompletionWeights {
+    fn default() -> Self {
+        Self {
+            text_stable: default_weight(),
+            streaming_ended: default_weight(),
+            send_enabled: default_weight(),
+            stop_gone: default_weight(),
+        }
+    }
+}
+
+/// When a service's answer counts as finished. With the default weights any
+/// two signals are enough.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompletionConfig {
+    // Present while the answer streams, e.g. [data-is-streaming='true']
+    #[serde(default)]
+    pub streaming_selectors: Vec<String>,
+    #[serde(default)]
+    pub stop_button_selectors: Vec<String>,
+    // The service's sendSelectors when empty
+    #[serde(default)]
+    pub send_button_selectors: Vec<String>,
+    // Text unchanged for this long counts as stable
+    #[serde(default = "default_stable_ms")]
+    pub stable_ms: u64,
+    #[serde(default = "default_min_length")]
+    pub min_length: usize,
+    #[serde(default)]
+    pub weights: CompletionWeights,
+    #[serde(default = "default_threshold")]
+    pub threshold: f64,
+    #[serde(default = "default_poll_ms")]
+    pub poll_ms: u64,
+    // Give up waiting and take what is there
+    #[serde(default = "default_max_duration_ms")]
+    pub max_duration_ms: u64,
+}
+
+fn default_stable_ms() -> u64 {
+    3000
+}
+
+fn default_min_length() -> usize {
+    1
+}
+
+fn default_threshold() -> f64 {
+    1.0
+}
+
+fn default_poll_ms() -> u64 {
+    500
+}
+
+fn default_max_duration_ms() -> u64 {
+    300000
+}
+
+impl Default for CompletionConfig {
+    fn default() -> Self {
+        Self {
+            streaming_selectors: Vec::new(),
+            stop_button_selectors: Vec::new(),
+            send_button_selectors: Vec::new(),
+            stable_ms: default_stable_ms(),
+            min_length: default_min_length(),
+            weights: CompletionWeights::default(),
+            threshold: default_threshold(),
+            poll_ms: default_poll_ms(),
+            max_duration_ms: default_max_duration_ms(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompletionSignal {
+    TextStable,
+    StreamingEnded,
+    SendEnabled,
+    StopGone,
+}
+
+impl fmt::Display for CompletionSignal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CompletionSignal::TextStable => "text_stable",
+            CompletionSignal::StreamingEnded => "streaming_ended",
+            CompletionSignal::SendEnabled => "send_enabled",
+            CompletionSignal::StopGone => "stop_gone",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CompletionReason {
+    // The weighted signals reached the threshold
+    Signals { signals: Vec<CompletionSignal>, score: f64 },
+    MaxDuration,
+}
+
+impl fmt::Display for CompletionReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompletionReason::Signals { signals, score } => {
+                let names: Vec<String> = signals.iter().map(ToString::to_string).collect();
+                write!(f, "{} ({:.2})", names.join("+"), score)
+            }
+            CompletionReason::MaxDuration => write!(f, "max duration reached"),
+        }
+    }
+}
+
+/// One look at the service page.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CompletionProbe {
+    pub text: String,
+    pub streaming: bool,
+    pub stop_visible: bool,
+    // None when no send button was found
+    pub send_enabled: Option<bool>,
+}
+
+/// Turns a series of probes into a decision. Nothing counts until the answer
+/// has started: something streamed, or the text differs from the response
+/// seen before sending (the first probe when that is unknown).
+pub struct CompletionDetector<'a> {
+    config: &'a CompletionConfig,
+    initial: Option<String>,
+    last_text: String,
+    changed_at: u64,
+    started: bool,
+    streaming_seen: bool,
+    stop_seen: bool,
+}
+
+impl<'a> CompletionDetector<'a> {
+    pub fn new(config: &'a CompletionConfig, before_send: Option<String>) -> Self {
+        Self {
+            config,
+            initial: before_send,
+            last_text: String::new(),
+            changed_at: 0,
+            started: false,
+            streaming_seen: false,
+            stop_seen: false,
+        }
+    }
+
+    /// Feeds a probe taken `at_ms` after waiting began; returns why the answer
+    /// is complete once it is.
+    pub fn observe(&mut self, probe: &CompletionProbe, at_ms: u64) -> Option<CompletionReason> {
+        let differs = match &self.initial {
+            Some(initial) => probe.text != *initial,
+            None => {
+                self.initial = Some(probe.text.clone());
+                false
+            }
+        };
+        if probe.text != self.last_text {
+            self.last_text = probe.text.clone();
+            self.changed_at = at_ms;
+        }
+        self.streaming_seen |= probe.streaming;
+        self.stop_seen |= probe.stop_visible;
+        self.started |= probe.streaming || probe.stop_visible || differs;
+
+        if at_ms >= self.config.max_duration_ms {
+            return Some(CompletionReason::MaxDuration);
+        }
+        if !self.started || probe.text.trim().chars().count() < self.config.min_length {
+            return None;
+        }
+
+        let weights = &self.config.weights;
+        let candidates = [
+            (CompletionSignal::TextStable, at_ms - self.changed_at >= self.config.stable_ms, weights.text_stable),
+            (CompletionSignal::StreamingEnded, self.streaming_seen && !probe.streaming, weights.streaming_ended),
+            (CompletionSignal::SendEnabled, probe.send_enabled == Some(true), weights.send_enabled),
+            (CompletionSignal::StopGone, self.stop_seen && !probe.stop_visible, weights.stop_gone),
+        ];
+        let fired: Vec<(CompletionSignal, f64)> = candidates
+            .into_iter()
+            .filter(|(_, fired, _)| *fired)
+            .map(|(signal, _, weight)| (signal, weight))
+            .collect();
+        let score: f64 = fired.iter().map(|(_, weight)| weight).sum();
+        (score >= self.config.threshold).then(|| CompletionReason::Signals {
+            signals: fired.into_iter().map(|(signal, _)| signal).collect(),
+            score,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CompletionResult {
+    pub label: String,
+    pub service_id: String,
+    pub reason: CompletionReason,
+    pub text_length: usize,
+    pub elapsed_ms: u64,
+}
+
+fn probe_args(selectors: &ServiceSelectors) -> Value {
+    let config = &selectors.completion;
+    let send_selectors = if config.send_button_selectors.is_empty() {
+        &selectors.send_selectors
+    } else {
+        &config.send_button_selectors
+    };
+    json!({
+        "responseSelectors": selectors.response_selectors,
+        "excludeSelectors": selectors.cleanup.exclude_selectors,
+        "streamingSelectors": config.streaming_selectors,
+        "stopSelectors": config.stop_button_selectors,
+        "sendSelectors": send_selectors,
+    })
+}
+
+/// The latest response text on the page, taken right before a prompt is sent
+/// so the next wait knows what the answer has to differ from.
+pub async fn response_before_send(window: &tauri::Window, selectors: &ServiceSelectors) -> Option<String> {
+    match run_script(window, "completion_probe", &probe_args(selectors), Duration::from_millis(PROBE_TIMEOUT_MS)).await {
+        Ok(value) => serde_json::from_value::<CompletionProbe>(value).ok().map(|probe| probe.text),
+        Err(e) => {
+            log_warning("COMPLETION", &format!("{}: could not read the response before sending: {}", window.label(), e));
+            None
+        }
+    }
+}
+
+/// Polls the service window until its completion config says the answer is done.
+pub async fn wait_for_completion_in(
+    app: &tauri::AppHandle,
+    label: &str,
+    service_id: Option<String>,
+) -> Result<CompletionResult, String> {
+    let window = app
+        .get_window(label)
+        .ok_or_else(|| "Window not found".to_string())?;
+    let session = app.state::<WebviewSessions>().get(label);
+    let service_id = service_id
+        .or_else(|| session.as_ref().and_then(|s| s.service_id.clone()))
+        .ok_or_else(|| format!("No service known for window '{}'", label))?;
+    let selectors = service_selectors(&service_id)?;
+    let args = probe_args(&selectors);
+    let config = selectors.completion;
+
+    let started = Instant::now();
+    let started_at = chrono::Utc::now().to_rfc3339();
+    let before_send = session.as_ref().and_then(|s| s.response_before_send.clone());
+    let mut detector = CompletionDetector::new(&config, before_send);
+    let mut text_length = 0;
+    let reason = loop {
+        let elapsed = started.elapsed().as_millis() as u64;
+        let probe = run_script(&window, "completion_probe", &args, Duration::from_millis(PROBE_TIMEOUT_MS))
+            .await
+            .and_then(|value| serde_json::from_value::<CompletionProbe>(value).map_err(|e| e.to_string()));
+        match probe {
+            Ok(probe) => {
+                text_length = probe.text.len();
+                if let Some(reason) = detector.observe(&probe, elapsed) {
+                    break reason;
+                }
+            }
+            Err(e) if elapsed >= config.max_duration_ms => {
+                log_warning("COMPLETION", &format!("{}: probe failed at the deadline: {}", label, e));
+                break CompletionReason::MaxDuration;
+            }
+            Err(e) => log_warning("COMPLETION", &format!("{}: probe failed: {}", label, e)),
+        }
+        tokio::time::sleep(Duration::from_millis(config.poll_ms)).await;
+    };
+
+    let result = CompletionResult {
+        label: label.to_string(),
+        service_id,
+        reason,
+        text_length,
+        elapsed_ms: started.elapsed().as_millis() as u64,
+    };
+    app.state::<WebviewSessions>().update(label, |session| {
+        session.answer_started_at = Some(started_at);
+        session.completion_reason = Some(result.reason.to_string());
+    });
+    match result.reason {
+        CompletionReason::MaxDuration => {
+            let note = format!("{}: stopped waiting after {} ms; the answer may be incomplete", label, result.elapsed_ms);
+            log_warning("COMPLETION", &note);
+            if let Some(run_id) = session.and_then(|s| s.run_id) {
+                let _ = app.state::<RunRegistry>().update(&run_id, |run| run.warnings.push(note));
+            }
+        }
+        _ => log_success(
+            "COMPLETION",
+            &format!("{}: {} chars complete after {} ms: {}", label, result.text_length, result.elapsed_ms, result.reason),
+        ),
+    }
+    Ok(result)
+}
+
+#[command]
+pub async fn wait_for_completion(
+    app: tauri::AppHandle,
+    label: String,
+    service_id: Option<String>,
+) -> Result<CompletionResult, String> {
+    wait_for_completion_in(&app, &label, service_id).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn probe(text: &str, streaming: bool, stop_visible: bool, send_enabled: Option<bool>) -> CompletionProbe {
+        CompletionProbe { text: text.to_string(), streaming, stop_visible, send_enabled }
+    }
+
+    #[test]
+    fn test_nothing_counts_before_the_answer_starts() {
+        let config = CompletionConfig::default();
+        let mut detector = CompletionDetector::new(&config, None);
+        // The previous answer sits there, stable, with the send button enabled
+        for at in [0, 2000, 4000, 8000] {
+            assert_eq!(detector.observe(&probe("Old answer", false, false, Some(true)), at), None);
+        }
+        assert_eq!(detector.observe(&probe("New", true, true, Some(false)), 8500), None);
+        let reason = detector.observe(&probe("New answer.", false, false, Some(false)), 9000).unwrap();
+        assert_eq!(
+            reason,
+            CompletionReason::Signals { signals: vec![CompletionSignal::StreamingEnded, CompletionSignal::StopGone], score: 1.0 }
+        );
+        assert_eq!(reason.to_string(), "streaming_ended+stop_gone (1.00)");
+    }
+
+    #[test]
+    fn test_stability_plus_send_button() {
+        let config = CompletionConfig::default();
+        let mut detector = CompletionDetector::new(&config, None);
+        assert_eq!(detector.observe(&probe("", false, false, Some(false)), 0), None);
+        assert_eq!(detector.observe(&probe("Answer", false, false, Some(true)), 500), None);
+        assert_eq!(detector.observe(&probe("Answer", false, false, Some(true)), 3000), None);
+        let reason = detector.observe(&probe("Answer", false, false, Some(true)), 3500).unwrap();
+        assert_eq!(
+            reason,
+            CompletionReason::Signals { signals: vec![CompletionSignal::TextStable, CompletionSignal::SendEnabled], score: 1.0 }
+        );
+    }
+
+    #[test]
+    fn test_weights_threshold_and_max_duration() {
+        let config = CompletionConfig {
+            weights: CompletionWeights { text_stable: 1.0, ..CompletionWeights::default() },
+            max_duration_ms: 10000,
+            ..CompletionConfig::default()
+        };
+        let mut detector = CompletionDetector::new(&config, None);
+        assert_eq!(detector.observe(&probe("", false, false, None), 0), None);
+        assert_eq!(detector.observe(&probe("Partial", false, false, None), 1000), None);
+        assert!(matches!(detector.observe(&probe("Partial", false, false, None), 4000), Some(CompletionReason::Signals { .. })));
+
+        let mut stuck = CompletionDetector::new(&config, None);
+        assert_eq!(stuck.observe(&probe("", true, true, None), 0), None);
+        assert_eq!(stuck.observe(&probe("", true, true, None), 10000), Some(CompletionReason::MaxDuration));
+    }
+
+    #[test]
+    fn test_short_answer_done_before_the_first_probe() {
+        let config = CompletionConfig::default();
+        let mut detector = CompletionDetector::new(&config, Some("Old answer".to_string()));
+        assert_eq!(detector.observe(&probe("Yes.", false, false, Some(true)), 0), None);
+        let reason = detector.observe(&probe("Yes.", false, false, Some(true)), 3000).unwrap();
+        assert_eq!(
+            reason,
+            CompletionReason::Signals { signals: vec![CompletionSignal::TextStable, CompletionSignal::SendEnabled], score: 1.0 }
+        );
+    }
+}
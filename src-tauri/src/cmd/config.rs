@@ -1,20 +1,293 @@
 use tauri::command;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::HashMap;
 use std::path::PathBuf;
+use std::sync::OnceLock;
+use crate::cmd::background::WindowMode;
+use crate::cmd::cleanup::CleanupRules;
+use crate::cmd::completion::CompletionConfig;
+use crate::cmd::guard::OffDomainAction;
+use crate::cmd::injection::{InjectionMode, InputStrategy, TypingOptions};
+use crate::cmd::service_errors::ErrorSignature;
 use crate::utils::{log_with_context, log_error, log_success};
 
-#[command]
-pub async fn load_webai_selectors() -> Result<String, String> {
-    log_with_context("CONFIG", "Loading WebAI selectors configuration");
-    
-    // Try multiple paths to find the config file
-    let config_paths = vec![
+// Typed view of webai-selectors.json used by the backend itself
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SelectorsConfig {
+    #[serde(default)]
+    pub version: String,
+    #[serde(default)]
+    pub services: HashMap<String, ServiceSelectors>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceSelectors {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub url: String,
+    #[serde(default)]
+    pub input_selectors: Vec<String>,
+    #[serde(default)]
+    pub send_selectors: Vec<String>,
+    #[serde(default)]
+    pub response_selectors: Vec<String>,
+    // How prompts are written into the matched input element
+    #[serde(default)]
+    pub input_strategy: InputStrategy,
+    // Instant fill or keystroke typing, unless the caller picks one
+    #[serde(default)]
+    pub injection_mode: InjectionMode,
+    #[serde(default)]
+    pub typing: TypingOptions,
+    #[serde(default)]
+    pub login: LoginSelectors,
+    #[serde(default)]
+    pub attachments: AttachmentSelectors,
+    #[serde(default)]
+    pub continuation: ContinuationSelectors,
+    // Signals, weights and thresholds that decide when an answer is done
+    #[serde(default)]
+    pub completion: CompletionConfig,
+    // Chrome removed from every extracted answer
+    #[serde(default)]
+    pub cleanup: CleanupRules,
+    #[serde(default)]
+    pub model_picker: ModelPicker,
+    #[serde(default)]
+    pub new_chat: NewChatSelectors,
+    // User and assistant messages, walked when importing a whole conversation
+    #[serde(default)]
+    pub turns: TurnSelectors,
+    // Limit, capacity, network and refusal pages checked after each response
+    #[serde(default)]
+    pub error_signatures: Vec<ErrorSignature>,
+    // Mode used instead of the run's when the run asks for background windows
+    #[serde(default)]
+    pub background_mode: Option<WindowMode>,
+    // Hosts (and their subdomains) the service window may navigate to
+    #[serde(default)]
+    pub allowed_domains: Vec<String>,
+    #[serde(default)]
+    pub off_domain_action: OffDomainAction,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoginSelectors {
+    // Any match means the chat UI is usable
+    #[serde(default)]
+    pub logged_in_selectors: Vec<String>,
+    #[serde(default)]
+    pub logged_out_selectors: Vec<String>,
+    // Captcha / Cloudflare interstitials; built-in defaults are always checked too
+    #[serde(default)]
+    pub challenge_selectors: Vec<String>,
+    // Substrings of the page URL that mean we landed on a sign-in page
+    #[serde(default)]
+    pub login_url_patterns: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttachmentSelectors {
+    // Hidden <input type="file"> elements that take uploads
+    #[serde(default)]
+    pub file_input_selectors: Vec<String>,
+    // Elements that accept dropped files when no file input is present
+    #[serde(default)]
+    pub drop_target_selectors: Vec<String>,
+    #[serde(default = "default_max_file_size_mb")]
+    pub max_file_size_mb: u64,
+    #[serde(default = "default_max_files")]
+    pub max_files: usize,
+    // Lowercase extensions without the dot; empty accepts anything
+    #[serde(default)]
+    pub allowed_extensions: Vec<String>,
+    // Extensions pasted into the prompt as text instead of uploaded
+    #[serde(default)]
+    pub paste_as_text_extensions: Vec<String>,
+    // Time given to the page to upload files before the prompt is sent
+    #[serde(default = "default_upload_settle_ms")]
+    pub upload_settle_ms: u64,
+}
+
+fn default_max_file_size_mb() -> u64 {
+    10
+}
+
+fn default_max_files() -> usize {
+    5
+}
+
+fn default_upload_settle_ms() -> u64 {
+    3000
+}
+
+impl Default for AttachmentSelectors {
+    fn default() -> Self {
+        Self {
+            file_input_selectors: Vec::new(),
+            drop_target_selectors: Vec::new(),
+            max_file_size_mb: default_max_file_size_mb(),
+            max_files: default_max_files(),
+            allowed_extensions: Vec::new(),
+            paste_as_text_extensions: Vec::new(),
+            upload_settle_ms: default_upload_settle_ms(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelPicker {
+    // Button that opens the model menu
+    #[serde(default)]
+    pub menu_selectors: Vec<String>,
+    // Entries of the opened menu
+    #[serde(default)]
+    pub option_selectors: Vec<String>,
+    // Element showing the active model's name
+    #[serde(default)]
+    pub active_selectors: Vec<String>,
+    // Model id used in chain steps (`chatgpt:o1`) -> labels it appears under
+    #[serde(default)]
+    pub models: HashMap<String, Vec<String>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewChatSelectors {
+    // "New chat" buttons, tried before navigating
+    #[serde(default)]
+    pub selectors: Vec<String>,
+    // Page that always opens an empty conversation
+    #[serde(default)]
+    pub url: Option<String>,
+    // Substrings of the URL once a conversation has a permalink
+    #[serde(default)]
+    pub conversation_url_patterns: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TurnSelectors {
+    // Each list is tried in order; the first selector with matches is used
+    #[serde(default)]
+    pub user_selectors: Vec<String>,
+    #[serde(default)]
+    pub assistant_selectors: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContinuationSelectors {
+    #[serde(default)]
+    pub button_selectors: Vec<String>,
+    // Exact button labels (case-insensitive), e.g. "Continue generating"
+    #[serde(default)]
+    pub button_texts: Vec<String>,
+    // Present while the answer is still streaming (stop buttons, spinners)
+    #[serde(default)]
+    pub generating_selectors: Vec<String>,
+    // Endings that mean the answer was cut off
+    #[serde(default)]
+    pub truncation_markers: Vec<String>,
+    // Sent when the answer is truncated but there is no button to click
+    #[serde(default = "default_continue_prompt")]
+    pub continue_prompt: String,
+    #[serde(default = "default_max_continuations")]
+    pub max_continuations: u32,
+    // Text unchanged for this long (and nothing generating) ends a fragment
+    #[serde(default = "default_settle_ms")]
+    pub settle_ms: u64,
+    #[serde(default = "default_fragment_timeout_ms")]
+    pub fragment_timeout_ms: u64,
+}
+
+fn default_continue_prompt() -> String {
+    "continue".to_string()
+}
+
+fn default_max_continuations() -> u32 {
+    3
+}
+
+fn default_settle_ms() -> u64 {
+    3000
+}
+
+fn default_fragment_timeout_ms() -> u64 {
+    180000
+}
+
+impl Default for ContinuationSelectors {
+    fn default() -> Self {
+        Self {
+            button_selectors: Vec::new(),
+            button_texts: Vec::new(),
+            generating_selectors: Vec::new(),
+            truncation_markers: Vec::new(),
+            continue_prompt: default_continue_prompt(),
+            max_continuations: default_max_continuations(),
+            settle_ms: default_settle_ms(),
+            fragment_timeout_ms: default_fragment_timeout_ms(),
+        }
+    }
+}
+
+// Copy of the selectors config shipped with a packaged build
+static BUNDLED_SELECTORS: OnceLock<PathBuf> = OnceLock::new();
+
+/// Remembers where the bundled `webai-selectors.json` resource lives. Packaged
+/// builds have no `config/` directory next to the working directory.
+pub fn register_bundled_config(app: &tauri::App) {
+    match app.path_resolver().resolve_resource("../config/webai-selectors.json") {
+        Some(path) => {
+            let _ = BUNDLED_SELECTORS.set(path);
+        }
+        None => log_error("CONFIG", "Could not resolve the bundled webai-selectors.json"),
+    }
+}
+
+/// Development copies first, then the bundled resource.
+fn selectors_config_paths() -> Vec<PathBuf> {
+    let mut paths = vec![
         PathBuf::from("./config/webai-selectors.json"),
         PathBuf::from("../config/webai-selectors.json"),
         PathBuf::from("config/webai-selectors.json"),
     ];
+    paths.extend(BUNDLED_SELECTORS.get().cloned());
+    paths
+}
+
+pub fn read_selectors_config() -> Result<SelectorsConfig, String> {
+    for path in selectors_config_paths() {
+        if let Ok(content) = std::fs::read_to_string(&path) {
+            return serde_json::from_str(&content)
+                .map_err(|e| format!("Invalid selectors config {:?}: {}", path, e));
+        }
+    }
+
+    Err("No webai-selectors.json found".to_string())
+}
+
+pub fn service_selectors(service_id: &str) -> Result<ServiceSelectors, String> {
+    read_selectors_config()?
+        .services
+        .remove(service_id)
+        .ok_or_else(|| format!("Service '{}' is not configured", service_id))
+}
+
+#[command]
+pub async fn load_webai_selectors() -> Result<String, String> {
+    log_with_context("CONFIG", "Loading WebAI selectors configuration");
     
-    for path in config_paths {
+    // Try multiple paths to find the config file
+    for path in selectors_config_paths() {
         log_with_context("CONFIG", &format!("Trying config path: {:?}", path));
         
         match std::fs::read_to_string(&path) {
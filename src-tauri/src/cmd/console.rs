@@ -0,0 +1,154 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use tauri::{command, Manager};
+use crate::cmd::sessions::WebviewSessions;
+use crate::utils::log_with_context;
+
+// Entries kept per window for get_webview_console
+const CONSOLE_BUFFER_SIZE: usize = 500;
+// Longest message accepted from a page
+const MAX_MESSAGE_LENGTH: usize = 4000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConsoleLevel {
+    Debug,
+    Log,
+    Info,
+    Warn,
+    Error,
+}
+
+// What the page sends
+#[derive(Debug, Clone, Deserialize)]
+pub struct RawConsoleEntry {
+    pub level: ConsoleLevel,
+    pub message: String,
+    pub source: Option<String>,
+    pub timestamp: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConsoleEntry {
+    pub label: String,
+    pub service_id: Option<String>,
+    pub level: ConsoleLevel,
+    pub message: String,
+    pub source: Option<String>,
+    pub timestamp: String,
+}
+
+pub struct WebviewConsole {
+    buffers: Mutex<HashMap<String, VecDeque<ConsoleEntry>>>,
+    // Entries at or above this level are also written to the backend log
+    log_level: Mutex<ConsoleLevel>,
+}
+
+impl Default for WebviewConsole {
+    fn default() -> Self {
+        Self {
+            buffers: Mutex::new(HashMap::new()),
+            log_level: Mutex::new(ConsoleLevel::Log),
+        }
+    }
+}
+
+impl WebviewConsole {
+    pub fn push(&self, entry: ConsoleEntry) {
+        let mut buffers = self.buffers.lock().unwrap();
+        let buffer = buffers.entry(entry.label.clone()).or_default();
+        if buffer.len() >= CONSOLE_BUFFER_SIZE {
+            buffer.pop_front();
+        }
+        buffer.push_back(entry);
+    }
+
+    pub fn recent(&self, label: &str, min_level: ConsoleLevel, limit: usize) -> Vec<ConsoleEntry> {
+        let buffers = self.buffers.lock().unwrap();
+        let mut entries: Vec<ConsoleEntry> = buffers
+            .get(label)
+            .map(|buffer| buffer.iter().rev().filter(|e| e.level >= min_level).take(limit).cloned().collect())
+            .unwrap_or_default();
+        entries.reverse();
+        entries
+    }
+
+    pub fn clear(&self, label: &str) {
+        self.buffers.lock().unwrap().remove(label);
+    }
+
+    pub fn log_level(&self) -> ConsoleLevel {
+        *self.log_level.lock().unwrap()
+    }
+}
+
+#[command]
+pub fn webview_console_report(
+    window: tauri::Window,
+    console: tauri::State<'_, WebviewConsole>,
+    sessions: tauri::State<'_, WebviewSessions>,
+    entries: Vec<RawConsoleEntry>,
+) {
+    // The label comes from the calling window, never from the page payload
+    let label = window.label().to_string();
+    let service_id = sessions.get(&label).and_then(|s| s.service_id);
+    let log_level = console.log_level();
+
+    for raw in entries {
+        let mut message = raw.message;
+        if message.len() > MAX_MESSAGE_LENGTH {
+            let mut cut = MAX_MESSAGE_LENGTH;
+            while !message.is_char_boundary(cut) {
+                cut -= 1;
+            }
+            message.truncate(cut);
+        }
+        let timestamp = raw
+            .timestamp
+            .and_then(|ms| chrono::DateTime::<chrono::Utc>::from_timestamp_millis(ms as i64))
+            .unwrap_or_else(chrono::Utc::now)
+            .to_rfc3339();
+
+        let entry = ConsoleEntry {
+            label: label.clone(),
+            service_id: service_id.clone(),
+            level: raw.level,
+            message,
+            source: raw.source,
+            timestamp,
+        };
+
+        if entry.level >= log_level {
+            log_with_context(
+                "WEBVIEW_CONSOLE",
+                &format!(
+                    "[{}/{}] {:?}: {}{}",
+                    entry.label,
+                    entry.service_id.as_deref().unwrap_or("-"),
+                    entry.level,
+                    entry.message,
+                    entry.source.as_deref().map(|s| format!(" ({})", s)).unwrap_or_default()
+                ),
+            );
+        }
+        console.push(entry);
+    }
+}
+
+#[command]
+pub async fn get_webview_console(
+    app: tauri::AppHandle,
+    label: String,
+    min_level: Option<ConsoleLevel>,
+    limit: Option<usize>,
+) -> Result<Vec<ConsoleEntry>, String> {
+    let console = app.state::<WebviewConsole>();
+    Ok(console.recent(&label, min_level.unwrap_or(ConsoleLevel::Debug), limit.unwrap_or(CONSOLE_BUFFER_SIZE)))
+}
+
+#[command]
+pub async fn set_console_log_level(app: tauri::AppHandle, level: ConsoleLevel) -> Result<String, String> {
+    *app.state::<WebviewConsole>().log_level.lock().unwrap() = level;
+    Ok(format!("Webview console entries at {:?} and above are now logged", level))
+}
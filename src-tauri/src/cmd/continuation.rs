@@ -0,0 +1,331 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::time::{Duration, Instant};
+use tauri::{command, Manager};
+use crate::cmd::config::{service_selectors, ContinuationSelectors, ServiceSelectors};
+use crate::cmd::injection::{inject_prompt_into, InjectionOptions};
+use crate::cmd::runs::RunRegistry;
+use crate::cmd::scripts::run_script;
+use crate::cmd::service_errors::{detect_service_error, ServiceErrorReport};
+use crate::cmd::sessions::WebviewSessions;
+use crate::utils::{log_with_context, log_success, log_warning};
+
+const POLL_INTERVAL_MS: u64 = 1000;
+const PROBE_TIMEOUT_MS: u64 = 5000;
+// Shorter shared runs are too likely to be coincidence
+const MIN_OVERLAP: usize = 12;
+// Only the seam is searched for overlap, not the whole answer
+const MAX_OVERLAP: usize = 4000;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct Probe {
+    text: String,
+    #[serde(default)]
+    html: String,
+    count: usize,
+    continue_selector: Option<String>,
+    generating: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContinueMethod {
+    Button,
+    Prompt,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ContinuationStep {
+    pub method: ContinueMethod,
+    // Button selector, or the prompt that was sent
+    pub trigger: String,
+    pub fragment_length: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LongResponse {
+    pub label: String,
+    pub service_id: String,
+    pub text: String,
+    pub fragments: Vec<String>,
+    pub continuations: Vec<ContinuationStep>,
+    pub complete: bool,
+    // "complete", "max_continuations", "timeout" or "service_error"
+    pub stop_reason: String,
+    // Set when a fragment turned out to be a limit or error page
+    pub failure: Option<ServiceErrorReport>,
+}
+
+fn unclosed_fence(text: &str) -> bool {
+    text.matches("```").count() % 2 == 1
+}
+
+/// The answer ends in a configured marker or inside an unclosed code block.
+pub fn is_truncated(text: &str, markers: &[String]) -> bool {
+    let text = text.trim_end();
+    !text.is_empty()
+        && (markers.iter().any(|marker| !marker.is_empty() && text.ends_with(marker.as_str())) || unclosed_fence(text))
+}
+
+fn strip_marker<'a>(text: &'a str, markers: &[String]) -> &'a str {
+    let text = text.trim_end();
+    markers
+        .iter()
+        .filter(|marker| !marker.is_empty())
+        .find_map(|marker| text.strip_suffix(marker.as_str()))
+        .unwrap_or(text)
+}
+
+/// Appends a continuation to what was collected so far. Handles a message that
+/// simply grew, text repeated across the seam, a re-opened code block and a
+/// restarted partial line, so the result reads as one answer.
+pub fn stitch(previous: &str, next: &str, markers: &[String]) -> String {
+    let head = strip_marker(previous, markers);
+    let ends_with_space = head.ends_with(char::is_whitespace) || previous.ends_with(char::is_whitespace);
+    let head = head.trim_end();
+    let mut rest = next.trim_start();
+    if head.is_empty() {
+        return next.trim_end().to_string();
+    }
+    if rest.starts_with(head.trim_start()) {
+        return rest.trim_end().to_string();
+    }
+
+    // The model re-opens the code block it was cut off in
+    if unclosed_fence(head) && rest.starts_with("```") {
+        rest = rest.split_once('\n').map(|(_, after)| after).unwrap_or("");
+    }
+
+    let longest = head.len().min(rest.len()).min(MAX_OVERLAP);
+    let overlap = (MIN_OVERLAP..=longest)
+        .rev()
+        .filter(|k| rest.is_char_boundary(*k))
+        .find(|k| head.ends_with(&rest[..*k]));
+    if let Some(k) = overlap {
+        return format!("{}{}", head, rest[k..].trim_end());
+    }
+
+    // The last, unfinished line is written again in full
+    let tail = head.rsplit('\n').next().unwrap_or(head).trim_start();
+    if tail.len() >= MIN_OVERLAP && rest.starts_with(tail) {
+        return format!("{}{}", &head[..head.len() - tail.len()], rest.trim_end());
+    }
+
+    let separator = if previous.trim_end_matches([' ', '\t']).ends_with('\n') || next.starts_with('\n') {
+        "\n"
+    } else if ends_with_space || next.starts_with(char::is_whitespace) {
+        " "
+    } else {
+        // Cut off mid-word
+        ""
+    };
+    format!("{}{}{}", head, separator, rest.trim_end())
+}
+
+fn probe_args(selectors: &ServiceSelectors, click: bool) -> serde_json::Value {
+    let continuation = &selectors.continuation;
+    json!({
+        "responseSelectors": selectors.response_selectors,
+        "excludeSelectors": selectors.cleanup.exclude_selectors,
+        "buttonSelectors": continuation.button_selectors,
+        "buttonTexts": continuation.button_texts,
+        "generatingSelectors": continuation.generating_selectors,
+        "click": click,
+    })
+}
+
+async fn probe(window: &tauri::Window, selectors: &ServiceSelectors, click: bool) -> Result<Probe, String> {
+    let value = run_script(
+        window,
+        "continuation_probe",
+        &probe_args(selectors, click),
+        Duration::from_millis(PROBE_TIMEOUT_MS),
+    )
+    .await?;
+    let mut current: Probe = serde_json::from_value(value).map_err(|e| format!("Invalid continuation probe: {}", e))?;
+    // Fragments are compared and stitched as the Markdown the chain passes on
+    current.text = if current.html.is_empty() {
+        selectors.cleanup.filter(&current.text)
+    } else {
+        selectors.cleanup.markdown(&current.html)
+    };
+    Ok(current)
+}
+
+/// Waits until the latest response stops changing and nothing is generating.
+/// With `after`, the response must first differ from that text (a new fragment
+/// has started); with `min_count`, that many responses must exist.
+async fn wait_for_fragment(
+    window: &tauri::Window,
+    selectors: &ServiceSelectors,
+    after: Option<&str>,
+    min_count: usize,
+) -> Result<Option<Probe>, String> {
+    let continuation: &ContinuationSelectors = &selectors.continuation;
+    let deadline = Instant::now() + Duration::from_millis(continuation.fragment_timeout_ms);
+    let mut last = Probe::default();
+    let mut stable_since = Instant::now();
+
+    while Instant::now() < deadline {
+        let current = match probe(window, selectors, false).await {
+            Ok(current) => current,
+            Err(e) => {
+                log_warning("CONTINUE", &format!("Probe failed: {}", e));
+                tokio::time::sleep(Duration::from_millis(POLL_INTERVAL_MS)).await;
+                continue;
+            }
+        };
+        if current.text != last.text || current.generating {
+            stable_since = Instant::now();
+        }
+        let started = current.count >= min_count && after.is_none_or(|text| current.text != text);
+        let settled = stable_since.elapsed() >= Duration::from_millis(continuation.settle_ms);
+        if started && settled && !current.text.trim().is_empty() {
+            return Ok(Some(current));
+        }
+        last = current;
+        tokio::time::sleep(Duration::from_millis(POLL_INTERVAL_MS)).await;
+    }
+    Ok(None)
+}
+
+/// Collects the whole answer in `label`: clicks the service's continue button
+/// or sends its continue prompt while the answer is cut off, up to
+/// `maxContinuations` times, and stitches the fragments together.
+pub async fn complete_response(
+    app: &tauri::AppHandle,
+    label: &str,
+    service_id: Option<String>,
+) -> Result<LongResponse, String> {
+    let window = app
+        .get_window(label)
+        .ok_or_else(|| "Window not found".to_string())?;
+    let session = app.state::<WebviewSessions>().get(label);
+    let service_id = service_id
+        .or_else(|| session.as_ref().and_then(|s| s.service_id.clone()))
+        .ok_or_else(|| format!("No service known for window '{}'", label))?;
+    let selectors = service_selectors(&service_id)?;
+    let continuation = selectors.continuation.clone();
+
+    let mut current = wait_for_fragment(&window, &selectors, None, 1)
+        .await?
+        .ok_or_else(|| format!("No response appeared in {}", label))?;
+    let mut response = LongResponse {
+        label: label.to_string(),
+        service_id: service_id.clone(),
+        text: current.text.clone(),
+        fragments: vec![current.text.clone()],
+        continuations: Vec::new(),
+        complete: false,
+        stop_reason: String::new(),
+        failure: None,
+    };
+
+    loop {
+        // Never continue a rate-limit or capacity message
+        if let Ok(Some(report)) = detect_service_error(app, label, Some(service_id.clone()), &current.text).await {
+            response.failure = Some(report);
+            response.stop_reason = "service_error".to_string();
+            break;
+        }
+        let truncated = is_truncated(&response.text, &continuation.truncation_markers);
+        if current.continue_selector.is_none() && !truncated {
+            response.complete = true;
+            response.stop_reason = "complete".to_string();
+            break;
+        }
+        if response.continuations.len() as u32 >= continuation.max_continuations {
+            response.stop_reason = "max_continuations".to_string();
+            break;
+        }
+
+        let step = if let Some(selector) = current.continue_selector.clone() {
+            log_with_context("CONTINUE", &format!("{}: clicking {}", label, selector));
+            probe(&window, &selectors, true).await?;
+            let next = wait_for_fragment(&window, &selectors, Some(&current.text), current.count).await?;
+            next.map(|next| (ContinueMethod::Button, selector, next))
+        } else {
+            log_with_context("CONTINUE", &format!("{}: answer looks truncated, asking to continue", label));
+            let options = InjectionOptions::default();
+            inject_prompt_into(app, label, &continuation.continue_prompt, Some(service_id.clone()), &[], &options).await?;
+            let next = wait_for_fragment(&window, &selectors, None, current.count + 1).await?;
+            next.map(|next| (ContinueMethod::Prompt, continuation.continue_prompt.clone(), next))
+        };
+
+        let Some((method, trigger, next)) = step else {
+            response.stop_reason = "timeout".to_string();
+            break;
+        };
+        // A button continues the same message; its new part is what was added
+        let fragment = match method {
+            ContinueMethod::Button => next.text.strip_prefix(current.text.as_str()).unwrap_or(&next.text).to_string(),
+            ContinueMethod::Prompt => next.text.clone(),
+        };
+        response.text = stitch(&response.text, &fragment, &continuation.truncation_markers);
+        response.continuations.push(ContinuationStep {
+            method,
+            trigger,
+            fragment_length: fragment.len(),
+        });
+        response.fragments.push(fragment);
+        current = next;
+    }
+
+    if response.complete {
+        log_success(
+            "CONTINUE",
+            &format!("{}: {} chars after {} continuation(s)", label, response.text.len(), response.continuations.len()),
+        );
+    } else {
+        let note = format!("Answer in {} may be incomplete ({})", label, response.stop_reason);
+        log_warning("CONTINUE", &note);
+        if let Some(run_id) = session.and_then(|s| s.run_id) {
+            let _ = app.state::<RunRegistry>().update(&run_id, |run| run.warnings.push(note));
+        }
+    }
+    Ok(response)
+}
+
+#[command]
+pub async fn continue_response(
+    app: tauri::AppHandle,
+    label: String,
+    service_id: Option<String>,
+) -> Result<LongResponse, String> {
+    complete_response(&app, &label, service_id).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn markers() -> Vec<String> {
+        vec!["(continued)".to_string()]
+    }
+
+    #[test]
+    fn test_truncation_markers_and_open_code_blocks() {
+        assert!(is_truncated("Part one (continued)\n", &markers()));
+        assert!(is_truncated("```rust\nfn main() {", &markers()));
+        assert!(!is_truncated("```rust\nfn main() {}\n```", &markers()));
+        assert!(!is_truncated("", &markers()));
+    }
+
+    #[test]
+    fn test_stitch_removes_overlap() {
+        let stitched = stitch("Alpha beta gamma delta epsilon", "gamma delta epsilon zeta eta", &markers());
+        assert_eq!(stitched, "Alpha beta gamma delta epsilon zeta eta");
+        // The same message grew after a continue button
+        assert_eq!(stitch("First part", "First part and more", &markers()), "First part and more");
+    }
+
+    #[test]
+    fn test_stitch_joins_code_and_partial_lines() {
+        let stitched = stitch("Code:\n```rust\nfn a() {}\n", "```rust\nfn b() {}\n```", &markers());
+        assert_eq!(stitched, "Code:\n```rust\nfn a() {}\nfn b() {}\n```");
+        let stitched = stitch("Intro.\nThe quick brown fo", "The quick brown fox jumps.", &markers());
+        assert_eq!(stitched, "Intro.\nThe quick brown fox jumps.");
+        assert_eq!(stitch("The answer is (continued)", "the rest.", &markers()), "The answer is the rest.");
+        assert_eq!(stitch("Cut mid-wo", "rd here", &markers()), "Cut mid-word here");
+    }
+}
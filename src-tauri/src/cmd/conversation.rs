@@ -0,0 +1,243 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::time::Duration;
+use tauri::{command, Manager};
+use crate::cmd::cleanup::CleanupRules;
+use crate::cmd::config::service_selectors;
+use crate::cmd::guard;
+use crate::cmd::provenance::{self, Provenance};
+use crate::cmd::scripts::{eval_script, run_script};
+use crate::cmd::sessions::WebviewSessions;
+use crate::utils::{log_with_context, log_warning};
+
+const BUTTON_TIMEOUT_MS: u64 = 5000;
+const TURNS_TIMEOUT_MS: u64 = 15000;
+// Time for the empty conversation to render before the login/input check
+const NEW_CHAT_SETTLE_MS: u64 = 2500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NewChatMethod {
+    Button,
+    Url,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NewChat {
+    pub method: NewChatMethod,
+    // Button selector or URL that was used
+    pub target: String,
+    pub previous_url: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TurnRole {
+    User,
+    Assistant,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RawTurn {
+    pub role: TurnRole,
+    pub html: String,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConversationTurn {
+    pub role: TurnRole,
+    pub markdown: String,
+    // Position among the non-empty turns, from 0
+    pub index: usize,
+}
+
+/// Every turn of the conversation open in a service window.
+#[derive(Debug, Clone, Serialize)]
+pub struct Transcript {
+    pub label: String,
+    pub service_id: String,
+    pub conversation_url: Option<String>,
+    pub turns: Vec<ConversationTurn>,
+    pub provenance: Provenance,
+}
+
+fn resolve_service(app: &tauri::AppHandle, label: &str, service_id: Option<String>) -> Result<String, String> {
+    service_id
+        .or_else(|| app.state::<WebviewSessions>().get(label).and_then(|s| s.service_id))
+        .ok_or_else(|| format!("No service known for window '{}'", label))
+}
+
+/// Whether `url` is a conversation permalink rather than the service's start page.
+pub fn is_conversation_url(url: &str, patterns: &[String]) -> bool {
+    patterns.iter().any(|pattern| !pattern.is_empty() && url.contains(pattern.as_str()))
+}
+
+/// Opens an empty conversation in the window: clicks the service's "new chat"
+/// button, or navigates to its new-chat URL (the service URL if none is set).
+pub async fn start_new_chat(app: &tauri::AppHandle, label: &str, service_id: &str) -> Result<NewChat, String> {
+    let window = app
+        .get_window(label)
+        .ok_or_else(|| "Window not found".to_string())?;
+    let selectors = service_selectors(service_id)?;
+    let new_chat = selectors.new_chat;
+    let previous_url = window.url().to_string();
+
+    let mut clicked = None;
+    if !new_chat.selectors.is_empty() && guard::has_ipc_access(app, &window.url()) {
+        let args = json!({ "selectors": new_chat.selectors });
+        match run_script(&window, "new_chat", &args, Duration::from_millis(BUTTON_TIMEOUT_MS)).await {
+            Ok(Value::String(selector)) => clicked = Some(selector),
+            Ok(_) => log_warning("NEW_CHAT", &format!("{}: no new chat button visible", label)),
+            Err(e) => log_warning("NEW_CHAT", &format!("{}: new chat button failed: {}", label, e)),
+        }
+    }
+
+    let result = match clicked {
+        Some(selector) => NewChat { method: NewChatMethod::Button, target: selector, previous_url },
+        None => {
+            let url = new_chat.url.unwrap_or(selectors.url);
+            if url.is_empty() {
+                return Err(format!("No new chat selector or URL configured for {}", service_id));
+            }
+            eval_script(&window, "navigate", &json!({ "url": url }))?;
+            NewChat { method: NewChatMethod::Url, target: url, previous_url }
+        }
+    };
+    log_with_context("NEW_CHAT", &format!("{}: new conversation via {:?} ({})", label, result.method, result.target));
+    tokio::time::sleep(Duration::from_millis(NEW_CHAT_SETTLE_MS)).await;
+    Ok(result)
+}
+
+/// The window's URL when it is a conversation permalink.
+pub fn conversation_url(app: &tauri::AppHandle, label: &str, service_id: &str) -> Result<Option<String>, String> {
+    let window = app
+        .get_window(label)
+        .ok_or_else(|| "Window not found".to_string())?;
+    let patterns = service_selectors(service_id)?.new_chat.conversation_url_patterns;
+    let url = window.url().to_string();
+    Ok(is_conversation_url(&url, &patterns).then_some(url))
+}
+
+/// Converts the page's turns to Markdown, dropping empty ones (placeholders
+/// of messages that have not rendered). Assistant turns keep their sources
+/// as footnotes; plain text is used when the HTML yields nothing.
+pub fn to_turns(raw: Vec<RawTurn>, rules: &CleanupRules) -> Vec<ConversationTurn> {
+    raw.into_iter()
+        .filter_map(|turn| {
+            let markdown = match turn.role {
+                TurnRole::User => rules.markdown(&turn.html),
+                TurnRole::Assistant => rules.markdown_with_sources(&turn.html).0,
+            };
+            let markdown = if markdown.trim().is_empty() { rules.filter(turn.text.trim()) } else { markdown };
+            (!markdown.is_empty()).then_some((turn.role, markdown))
+        })
+        .enumerate()
+        .map(|(index, (role, markdown))| ConversationTurn { role, markdown, index })
+        .collect()
+}
+
+pub async fn extract_conversation_in(
+    app: &tauri::AppHandle,
+    label: &str,
+    service_id: &str,
+) -> Result<Transcript, String> {
+    let window = app
+        .get_window(label)
+        .ok_or_else(|| "Window not found".to_string())?;
+    let selectors = service_selectors(service_id)?;
+    let turns = &selectors.turns;
+    if turns.user_selectors.is_empty() && turns.assistant_selectors.is_empty() {
+        return Err(format!("No turn selectors configured for {}", service_id));
+    }
+
+    let args = json!({
+        "userSelectors": turns.user_selectors,
+        "assistantSelectors": turns.assistant_selectors,
+        "excludeSelectors": selectors.cleanup.exclude_selectors,
+    });
+    let value = run_script(&window, "conversation_turns", &args, Duration::from_millis(TURNS_TIMEOUT_MS)).await?;
+    let raw: Vec<RawTurn> = serde_json::from_value(value).map_err(|e| format!("Invalid conversation turns: {}", e))?;
+    let turns = to_turns(raw, &selectors.cleanup);
+    if turns.is_empty() {
+        return Err(format!("No conversation turns found in {}", label));
+    }
+    log_with_context("CONVERSATION", &format!("{}: extracted {} turn(s)", label, turns.len()));
+    let answers: Vec<&str> = turns
+        .iter()
+        .filter(|turn| turn.role == TurnRole::Assistant)
+        .map(|turn| turn.markdown.as_str())
+        .collect();
+    let selector = selectors.turns.assistant_selectors.first().cloned();
+    let collected = provenance::collect(app, label, service_id, "conversation_turns", selector, &answers.join("\n\n"));
+    // The session's completion details belong to the last chain step, not to
+    // a conversation continued by hand
+    let provenance = Provenance {
+        completion_reason: None,
+        started_at: collected.finished_at.clone(),
+        ..collected
+    };
+    Ok(Transcript {
+        label: label.to_string(),
+        service_id: service_id.to_string(),
+        conversation_url: conversation_url(app, label, service_id)?,
+        turns,
+        provenance,
+    })
+}
+
+#[command]
+pub async fn new_chat(app: tauri::AppHandle, label: String, service_id: Option<String>) -> Result<NewChat, String> {
+    let service_id = resolve_service(&app, &label, service_id)?;
+    start_new_chat(&app, &label, &service_id).await
+}
+
+#[command]
+pub async fn get_conversation_url(
+    app: tauri::AppHandle,
+    label: String,
+    service_id: Option<String>,
+) -> Result<Option<String>, String> {
+    let service_id = resolve_service(&app, &label, service_id)?;
+    conversation_url(&app, &label, &service_id)
+}
+
+#[command]
+pub async fn extract_conversation(
+    app: tauri::AppHandle,
+    label: String,
+    service_id: Option<String>,
+) -> Result<Transcript, String> {
+    let service_id = resolve_service(&app, &label, service_id)?;
+    extract_conversation_in(&app, &label, &service_id).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_permalinks_count_as_conversations() {
+        let patterns = vec!["/c/".to_string()];
+        assert!(is_conversation_url("https://chatgpt.com/c/67a1-b2", &patterns));
+        assert!(!is_conversation_url("https://chatgpt.com/", &patterns));
+        assert!(!is_conversation_url("https://chatgpt.com/c/1", &[]));
+    }
+
+    #[test]
+    fn test_turns_are_converted_and_renumbered() {
+        let turn = |role, html: &str, text: &str| RawTurn { role, html: html.to_string(), text: text.to_string() };
+        let raw = vec![
+            turn(TurnRole::User, "<p>What is <code>ls</code>?</p>", "What is ls?"),
+            turn(TurnRole::Assistant, "", ""),
+            turn(TurnRole::Assistant, "<p>It <strong>lists</strong> files.</p>", "It lists files."),
+            turn(TurnRole::User, "", "Thanks"),
+        ];
+        let turns = to_turns(raw, &CleanupRules::default());
+        assert_eq!(turns.len(), 3);
+        assert_eq!(turns[0].markdown, "What is `ls`?");
+        assert_eq!(turns[1], ConversationTurn { role: TurnRole::Assistant, markdown: "It **lists** files.".to_string(), index: 1 });
+        assert_eq!(turns[2].markdown, "Thanks");
+        assert_eq!(turns[2].index, 2);
+    }
+}
@@ -1,7 +1,20 @@
 use tauri::command;
+use crate::cmd::attachments::{file_info, FileInfo};
+use crate::utils::log_warning;
 
+/// Describes dropped files so they can be attached to the next prompt;
+/// per-service limits are checked when they are attached.
 #[command]
-pub async fn process_dropped(paths: Vec<String>) -> Result<Vec<String>, String> {
-    // zwróćmy tylko nazwy – reszta w Twoich rękach
-    Ok(paths)
+pub async fn process_dropped(paths: Vec<String>) -> Result<Vec<FileInfo>, String> {
+    Ok(paths
+        .iter()
+        .filter_map(|path| match file_info(path) {
+            Ok(info) => Some(info),
+            Err(e) => {
+                // Foldery i nieczytelne ścieżki pomijamy
+                log_warning("DROP", &e);
+                None
+            }
+        })
+        .collect())
 }
@@ -0,0 +1,115 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::time::Duration;
+use tauri::{command, Manager};
+use crate::cmd::cleanup::CleanupRules;
+use crate::cmd::config::service_selectors;
+use crate::cmd::provenance::{self, Provenance};
+use crate::cmd::scripts::run_script;
+use crate::cmd::sessions::WebviewSessions;
+use crate::cmd::sources::Source;
+use crate::cmd::tables::{extract_tables, Table};
+use crate::utils::log_with_context;
+
+const EXTRACT_TIMEOUT_MS: u64 = 10000;
+
+#[derive(Debug, Clone, Deserialize)]
+struct RawResponse {
+    selector: String,
+    extraction_method: String,
+    html: String,
+    text: String,
+}
+
+/// The latest answer in a service window, as HTML and as the Markdown passed
+/// on to the next chain step.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExtractedResponse {
+    pub label: String,
+    pub service_id: String,
+    // Response selector that matched
+    pub selector: String,
+    pub html: String,
+    // Citation markers renumbered as [^n] footnotes, defined at the end
+    pub markdown: String,
+    pub text: String,
+    pub sources: Vec<Source>,
+    pub tables: Vec<Table>,
+    pub provenance: Provenance,
+}
+
+pub async fn extract_response_in(
+    app: &tauri::AppHandle,
+    label: &str,
+    service_id: Option<String>,
+) -> Result<ExtractedResponse, String> {
+    let window = app
+        .get_window(label)
+        .ok_or_else(|| "Window not found".to_string())?;
+    let service_id = service_id
+        .or_else(|| app.state::<WebviewSessions>().get(label).and_then(|s| s.service_id))
+        .ok_or_else(|| format!("No service known for window '{}'", label))?;
+    let selectors = service_selectors(&service_id)?;
+
+    let args = json!({
+        "responseSelectors": selectors.response_selectors,
+        "excludeSelectors": selectors.cleanup.exclude_selectors,
+    });
+    let value = run_script(&window, "extract_response", &args, Duration::from_millis(EXTRACT_TIMEOUT_MS)).await?;
+    let raw: Option<RawResponse> =
+        serde_json::from_value(value).map_err(|e| format!("Invalid extraction result: {}", e))?;
+    let raw = raw.ok_or_else(|| format!("No response element found in {}", label))?;
+
+    let (markdown, sources) = selectors.cleanup.markdown_with_sources(&raw.html);
+    let tables = extract_tables(&selectors.cleanup.document(&raw.html));
+    let provenance = provenance::collect(
+        app,
+        label,
+        &service_id,
+        &raw.extraction_method,
+        Some(raw.selector.clone()),
+        &markdown,
+    );
+    log_with_context(
+        "EXTRACT",
+        &format!(
+            "{}: {} chars of HTML -> {} chars of Markdown, {} source(s), {} table(s) via {}",
+            label,
+            raw.html.len(),
+            markdown.len(),
+            sources.len(),
+            tables.len(),
+            raw.selector
+        ),
+    );
+    Ok(ExtractedResponse {
+        label: label.to_string(),
+        service_id,
+        selector: raw.selector,
+        html: raw.html,
+        markdown,
+        text: selectors.cleanup.filter(&raw.text),
+        sources,
+        tables,
+        provenance,
+    })
+}
+
+#[command]
+pub async fn extract_response(
+    app: tauri::AppHandle,
+    label: String,
+    service_id: Option<String>,
+) -> Result<ExtractedResponse, String> {
+    extract_response_in(&app, &label, service_id).await
+}
+
+/// Converts pasted or saved HTML, with the service's cleanup rules when given.
+#[command]
+pub fn convert_html_to_markdown(html: String, service_id: Option<String>) -> Result<String, String> {
+    let rules = match service_id {
+        Some(service_id) => service_selectors(&service_id)?.cleanup,
+        None => CleanupRules::default(),
+    };
+    Ok(rules.markdown_with_sources(&html).0)
+}
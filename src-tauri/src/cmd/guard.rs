@@ -0,0 +1,255 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Mutex;
+use tauri::scope::ipc::RemoteDomainAccessScope;
+use tauri::{Invoke, Manager, Url};
+use crate::cmd::config::read_selectors_config;
+use crate::utils::{log_error, log_warning};
+
+// The only commands a service page may invoke; everything else is reserved
+// for the app's own window
+const REMOTE_COMMANDS: &[&str] = &["webview_eval_result", "webview_console_report"];
+
+/// Whether `command` may run when invoked from the window `label`.
+pub fn is_command_allowed(label: &str, command: &str) -> bool {
+    label == "main" || REMOTE_COMMANDS.contains(&command)
+}
+
+/// Wraps the generated invoke handler so service windows, which load remote
+/// pages, can only answer the eval bridge and report console output.
+pub fn guard_invoke<R: tauri::Runtime>(
+    handler: impl Fn(Invoke<R>) + Send + Sync + 'static,
+) -> impl Fn(Invoke<R>) + Send + Sync + 'static {
+    move |invoke: Invoke<R>| {
+        let label = invoke.message.window_ref().label().to_string();
+        let command = invoke.message.command().to_string();
+        if is_command_allowed(&label, &command) {
+            handler(invoke);
+        } else {
+            log_error("GUARD", &format!("Rejected '{}' invoked from {}", command, label));
+            invoke.resolver.reject(format!("Command '{}' is not available to this window", command));
+        }
+    }
+}
+
+pub const NAVIGATION_BLOCKED_EVENT: &str = "security://navigation-blocked";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OffDomainAction {
+    #[default]
+    Block,
+    Close,
+}
+
+#[derive(Debug, Clone)]
+pub struct NavigationPolicy {
+    pub label: String,
+    pub service_id: Option<String>,
+    pub allowed_domains: Vec<String>,
+    pub action: OffDomainAction,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NavigationBlocked {
+    pub label: String,
+    pub service_id: Option<String>,
+    pub url: String,
+    pub action: OffDomainAction,
+}
+
+/// Exact host or any subdomain of it, https/http only. `about:` pages are
+/// always allowed because every new window starts on one.
+pub fn is_navigation_allowed(url: &Url, allowed_domains: &[String]) -> bool {
+    match url.scheme() {
+        "about" => return true,
+        "https" | "http" => {}
+        _ => return false,
+    }
+
+    let host = match url.host_str() {
+        Some(host) => host.to_ascii_lowercase(),
+        None => return false,
+    };
+    allowed_domains.iter().any(|domain| {
+        let domain = domain.trim_start_matches('.').to_ascii_lowercase();
+        host == domain || host.ends_with(&format!(".{}", domain))
+    })
+}
+
+/// Domains a window may visit. Windows without a service get the union of
+/// every configured service, so they still cannot wander off.
+pub fn navigation_policy(label: &str, service_id: Option<&str>) -> Result<NavigationPolicy, String> {
+    let config = read_selectors_config()?;
+
+    let (allowed_domains, action) = match service_id {
+        Some(id) => {
+            let service = config
+                .services
+                .get(id)
+                .ok_or_else(|| format!("Service '{}' is not configured", id))?;
+            (service.allowed_domains.clone(), service.off_domain_action)
+        }
+        None => (
+            config.services.values().flat_map(|s| s.allowed_domains.clone()).collect(),
+            OffDomainAction::Block,
+        ),
+    };
+
+    if allowed_domains.is_empty() {
+        return Err(format!("No allowedDomains configured for {}", service_id.unwrap_or("any service")));
+    }
+
+    Ok(NavigationPolicy {
+        label: label.to_string(),
+        service_id: service_id.map(|s| s.to_string()),
+        allowed_domains,
+        action,
+    })
+}
+
+/// Policy used when the selectors config cannot be read: the window may only
+/// stay on the host it was opened with.
+pub fn fallback_policy(label: &str, service_id: Option<&str>, url: &Url) -> Result<NavigationPolicy, String> {
+    let host = url.host_str().ok_or_else(|| format!("URL '{}' has no host", url))?;
+    Ok(NavigationPolicy {
+        label: label.to_string(),
+        service_id: service_id.map(|s| s.to_string()),
+        allowed_domains: vec![host.to_string()],
+        action: OffDomainAction::Block,
+    })
+}
+
+/// (host, label) pairs already given a remote IPC scope.
+#[derive(Default)]
+pub struct IpcGrants {
+    granted: Mutex<HashSet<(String, String)>>,
+}
+
+/// Hosts listed in `dangerousRemoteDomainIpcAccess`. Only pages on these hosts
+/// can answer eval-bridge scripts or report console output.
+pub fn ipc_hosts(app: &tauri::AppHandle) -> Vec<String> {
+    app.config()
+        .tauri
+        .security
+        .dangerous_remote_domain_ipc_access
+        .iter()
+        .map(|scope| scope.domain.clone())
+        .collect()
+}
+
+pub fn has_ipc_access(app: &tauri::AppHandle, url: &Url) -> bool {
+    url.scheme() == "https"
+        && url
+            .host_str()
+            .map(|host| ipc_hosts(app).iter().any(|d| d == host))
+            .unwrap_or(false)
+}
+
+/// Tauri matches remote IPC scopes by exact window label, so every service
+/// window label gets its own scope for the IPC hosts inside its allowed
+/// domains. Scopes cannot be removed, so each (host, label) pair is granted
+/// once and reused when a window with the same label is recreated.
+pub fn grant_ipc_scope(app: &tauri::AppHandle, policy: &NavigationPolicy) {
+    let grants = app.state::<IpcGrants>();
+    let mut granted = grants.granted.lock().unwrap();
+    for host in ipc_hosts(app) {
+        let url: Url = match format!("https://{}/", host).parse() {
+            Ok(url) => url,
+            Err(_) => continue,
+        };
+        if is_navigation_allowed(&url, &policy.allowed_domains)
+            && granted.insert((host.clone(), policy.label.clone()))
+        {
+            app.ipc_scope().configure_remote_access(
+                RemoteDomainAccessScope::new(host)
+                    .allow_on_scheme("https")
+                    .add_window(policy.label.clone()),
+            );
+        }
+    }
+}
+
+/// Handler for `WindowBuilder::on_navigation`: returns false for off-domain
+/// URLs, logs them, emits `security://navigation-blocked` and closes the window
+/// when the service asks for it.
+pub fn navigation_handler(
+    app: tauri::AppHandle,
+    policy: NavigationPolicy,
+) -> impl Fn(Url) -> bool + Send + 'static {
+    move |url: Url| {
+        if is_navigation_allowed(&url, &policy.allowed_domains) {
+            return true;
+        }
+
+        log_error(
+            "GUARD",
+            &format!("Blocked navigation of {} to {} ({:?})", policy.label, url, policy.action),
+        );
+        let event = NavigationBlocked {
+            label: policy.label.clone(),
+            service_id: policy.service_id.clone(),
+            url: url.to_string(),
+            action: policy.action,
+        };
+
+        // Emitting or closing from inside the navigation callback could re-enter the event loop
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            let _ = app.emit_all(NAVIGATION_BLOCKED_EVENT, event.clone());
+            if event.action == OffDomainAction::Close {
+                if let Some(window) = app.get_window(&event.label) {
+                    if let Err(e) = window.close() {
+                        log_warning("GUARD", &format!("Failed to close {}: {}", event.label, e));
+                    }
+                }
+            }
+        });
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn domains() -> Vec<String> {
+        vec!["chatgpt.com".to_string(), ".openai.com".to_string()]
+    }
+
+    #[test]
+    fn test_allows_exact_domain_and_subdomains() {
+        assert!(is_navigation_allowed(&"https://chatgpt.com/c/123".parse().unwrap(), &domains()));
+        assert!(is_navigation_allowed(&"https://auth.openai.com/login".parse().unwrap(), &domains()));
+        assert!(is_navigation_allowed(&"about:blank".parse().unwrap(), &domains()));
+    }
+
+    #[test]
+    fn test_blocks_lookalike_and_foreign_domains() {
+        assert!(!is_navigation_allowed(&"https://evilchatgpt.com/".parse().unwrap(), &domains()));
+        assert!(!is_navigation_allowed(&"https://chatgpt.com.evil.io/".parse().unwrap(), &domains()));
+        assert!(!is_navigation_allowed(&"https://example.com/?next=chatgpt.com".parse().unwrap(), &domains()));
+    }
+
+    #[test]
+    fn test_blocks_non_web_schemes() {
+        assert!(!is_navigation_allowed(&"file:///etc/passwd".parse().unwrap(), &domains()));
+        assert!(!is_navigation_allowed(&"data:text/html,<h1>x</h1>".parse().unwrap(), &domains()));
+    }
+
+    #[test]
+    fn test_fallback_policy_keeps_the_opening_host() {
+        let policy = fallback_policy("ai-claude", Some("claude"), &"https://claude.ai/new".parse().unwrap()).unwrap();
+        assert!(is_navigation_allowed(&"https://claude.ai/chat/1".parse().unwrap(), &policy.allowed_domains));
+        assert!(!is_navigation_allowed(&"https://example.com/".parse().unwrap(), &policy.allowed_domains));
+    }
+
+    #[test]
+    fn test_service_windows_only_reach_bridge_commands() {
+        assert!(is_command_allowed("main", "save_artifacts"));
+        assert!(is_command_allowed("ai-chatgpt", "webview_eval_result"));
+        assert!(!is_command_allowed("ai-chatgpt", "save_artifacts"));
+        assert!(is_command_allowed("ai-claude", "webview_console_report"));
+        assert!(!is_command_allowed("ai-claude", "start_run"));
+    }
+}
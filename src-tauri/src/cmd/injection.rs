@@ -0,0 +1,329 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::time::Duration;
+use tauri::{command, Manager};
+use crate::cmd::attachments::{self, AttachmentRecord};
+use crate::cmd::completion;
+use crate::cmd::config::{service_selectors, ServiceSelectors};
+use crate::cmd::conversation::{self, NewChat};
+use crate::cmd::login;
+use crate::cmd::models::{self, ModelSelection};
+use crate::cmd::runs::RunRegistry;
+use crate::cmd::scripts::run_script;
+use crate::cmd::sessions::WebviewSessions;
+use crate::utils::{log_with_context, log_success, log_warning};
+
+const INJECTION_TIMEOUT_MS: u64 = 20000;
+const LOGIN_WAIT_TIMEOUT_MS: u64 = 10 * 60 * 1000;
+// Pause between filling the input and looking for an enabled send button
+const DEFAULT_SEND_DELAY_MS: u64 = 500;
+
+/// How text gets into a service's input. `Auto` picks one per matched element.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InputStrategy {
+    #[default]
+    Auto,
+    // Plain <textarea>/<input>: assign value, fire input + change
+    Textarea,
+    // React-controlled <textarea>/<input>: native value setter so React sees the change
+    React,
+    // Generic contenteditable: select all, execCommand('insertText')
+    Contenteditable,
+    // ProseMirror (ChatGPT, Claude): synthetic paste the editor handles itself
+    Prosemirror,
+    // Quill `.ql-editor` (Gemini): one <p> per line, then an input event
+    Quill,
+}
+
+/// Whether the prompt is written in one step or typed out key by key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InjectionMode {
+    #[default]
+    Instant,
+    // Keystroke events in chunks; for editors that garble one-step inserts
+    Typing,
+}
+
+/// Key combination that starts a new line without sending the message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NewlineKey {
+    #[default]
+    ShiftEnter,
+    Enter,
+    CtrlEnter,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TypingOptions {
+    // Characters typed between pauses
+    #[serde(default = "default_chunk_size")]
+    pub chunk_size: usize,
+    #[serde(default = "default_chunk_delay_ms")]
+    pub chunk_delay_ms: u64,
+    // Random extra pause of up to this many ms per chunk
+    #[serde(default = "default_jitter_ms")]
+    pub jitter_ms: u64,
+    #[serde(default)]
+    pub newline: NewlineKey,
+}
+
+fn default_chunk_size() -> usize {
+    12
+}
+
+fn default_chunk_delay_ms() -> u64 {
+    30
+}
+
+fn default_jitter_ms() -> u64 {
+    40
+}
+
+impl Default for TypingOptions {
+    fn default() -> Self {
+        Self {
+            chunk_size: default_chunk_size(),
+            chunk_delay_ms: default_chunk_delay_ms(),
+            jitter_ms: default_jitter_ms(),
+            newline: NewlineKey::default(),
+        }
+    }
+}
+
+/// Per-call choices from the frontend settings.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InjectionOptions {
+    // Overrides the service's configured injectionMode
+    #[serde(default)]
+    pub mode: Option<InjectionMode>,
+    // Refuse to send when the editor content differs from the prompt
+    #[serde(default)]
+    pub strict: bool,
+    // Model to switch to first, from a `service:model` chain step
+    #[serde(default)]
+    pub model: Option<String>,
+    // Start a new conversation so earlier turns do not leak into this one
+    #[serde(default)]
+    pub fresh_context: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InjectionOutcome {
+    #[serde(default)]
+    pub mode: InjectionMode,
+    pub strategy: InputStrategy,
+    pub input_selector: String,
+    // None when no send button was clickable and Enter was used instead
+    pub send_selector: Option<String>,
+    pub sent_with: String,
+    // The input contained the prompt after filling it
+    pub verified: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InjectionReport {
+    pub label: String,
+    pub service_id: String,
+    pub prompt_length: usize,
+    #[serde(flatten)]
+    pub outcome: InjectionOutcome,
+    pub attachments: Vec<AttachmentRecord>,
+    pub model: Option<ModelSelection>,
+    pub new_chat: Option<NewChat>,
+}
+
+/// Arguments for the `inject_prompt` script, built from the service's selector config.
+pub fn injection_args(prompt: &str, selectors: &ServiceSelectors, options: &InjectionOptions) -> Value {
+    json!({
+        "prompt": prompt,
+        "mode": options.mode.unwrap_or(selectors.injection_mode),
+        "strict": options.strict,
+        "strategy": selectors.input_strategy,
+        "typing": selectors.typing,
+        "inputSelectors": selectors.input_selectors,
+        "sendSelectors": selectors.send_selectors,
+        "sendDelayMs": DEFAULT_SEND_DELAY_MS,
+    })
+}
+
+/// Script timeout: typing takes time proportional to the prompt length.
+pub fn injection_timeout(prompt: &str, mode: InjectionMode, typing: &TypingOptions) -> Duration {
+    let typing_ms = match mode {
+        InjectionMode::Instant => 0,
+        InjectionMode::Typing => {
+            let chunks = prompt.chars().count().div_ceil(typing.chunk_size.max(1)) as u64;
+            chunks * (typing.chunk_delay_ms + typing.jitter_ms + 5)
+        }
+    };
+    Duration::from_millis(INJECTION_TIMEOUT_MS + typing_ms)
+}
+
+/// Types `prompt` into the service window and sends it, after waiting for the
+/// user to log in if needed. Files in `attachment_paths` are uploaded first
+/// (or pasted above the prompt for text types) within the service's limits,
+/// after opening a new conversation and switching to `options.model` when
+/// asked to. Reports which input and send selector worked.
+pub async fn inject_prompt_into(
+    app: &tauri::AppHandle,
+    label: &str,
+    prompt: &str,
+    service_id: Option<String>,
+    attachment_paths: &[String],
+    options: &InjectionOptions,
+) -> Result<InjectionReport, String> {
+    let window = app
+        .get_window(label)
+        .ok_or_else(|| "Window not found".to_string())?;
+    let session = app.state::<WebviewSessions>().get(label);
+    let service_id = service_id
+        .or_else(|| session.as_ref().and_then(|s| s.service_id.clone()))
+        .ok_or_else(|| format!("No service known for window '{}'", label))?;
+    let selectors = service_selectors(&service_id)?;
+    if selectors.input_selectors.is_empty() {
+        return Err(format!("No inputSelectors configured for {}", service_id));
+    }
+    let prepared = attachments::prepare(attachment_paths, &selectors.attachments)
+        .map_err(|e| format!("Attachment rejected by {}: {}", service_id, e))?;
+
+    login::ensure_logged_in(app, label, LOGIN_WAIT_TIMEOUT_MS).await?;
+
+    let new_chat = if options.fresh_context {
+        let new_chat = conversation::start_new_chat(app, label, &service_id).await?;
+        login::ensure_logged_in(app, label, LOGIN_WAIT_TIMEOUT_MS).await?;
+        Some(new_chat)
+    } else {
+        None
+    };
+    let model = match options.model.as_deref() {
+        Some(model) => Some(models::select_model_in(app, label, &service_id, model).await?),
+        None => None,
+    };
+
+    let mut attached = Vec::new();
+    if !attachment_paths.is_empty() {
+        attachments::log_prepared(label, &prepared);
+        attached = attachments::upload(&window, &prepared.uploads, &selectors.attachments).await?;
+        attached.extend(attachments::text_records(&prepared.texts));
+    }
+    let prompt = attachments::prompt_with_texts(prompt, &prepared.texts);
+    let prompt = prompt.as_str();
+
+    let mode = options.mode.unwrap_or(selectors.injection_mode);
+    log_with_context(
+        "INJECTION",
+        &format!(
+            "Injecting {} chars into {} ({:?}, {:?})",
+            prompt.len(),
+            label,
+            selectors.input_strategy,
+            mode
+        ),
+    );
+    let before_send = completion::response_before_send(&window, &selectors).await;
+    // The model can change between prompts without going through select_model
+    let active_model = match &model {
+        Some(selection) => Some(selection.active.clone()),
+        None => models::active_model(&window, &selectors.model_picker).await,
+    };
+    app.state::<WebviewSessions>().update(label, |session| {
+        session.response_before_send = before_send;
+        session.active_model = active_model.clone();
+    });
+    if let (Some(run_id), Some(active)) = (session.as_ref().and_then(|s| s.run_id.as_deref()), active_model) {
+        let _ = app.state::<RunRegistry>().update(run_id, |run| {
+            run.models.insert(service_id.clone(), active);
+        });
+    }
+    let args = injection_args(prompt, &selectors, options);
+    let timeout = injection_timeout(prompt, mode, &selectors.typing);
+    let value = run_script(&window, "inject_prompt", &args, timeout).await?;
+    let outcome: InjectionOutcome =
+        serde_json::from_value(value).map_err(|e| format!("Invalid injection result: {}", e))?;
+
+    let summary = format!(
+        "{}: input '{}' via {:?} ({:?}), sent with {} ({})",
+        label,
+        outcome.input_selector,
+        outcome.strategy,
+        outcome.mode,
+        outcome.sent_with,
+        outcome.send_selector.as_deref().unwrap_or("no send button")
+    );
+    if outcome.verified {
+        log_success("INJECTION", &summary);
+    } else {
+        log_warning("INJECTION", &format!("{} - input content could not be verified", summary));
+    }
+
+    if let Some(run_id) = session.and_then(|s| s.run_id) {
+        attachments::record_on_run(app, &run_id, &service_id, &attached);
+    }
+
+    Ok(InjectionReport {
+        label: label.to_string(),
+        service_id,
+        prompt_length: prompt.len(),
+        outcome,
+        attachments: attached,
+        model,
+        new_chat,
+    })
+}
+
+#[command]
+pub async fn inject_prompt(
+    app: tauri::AppHandle,
+    label: String,
+    prompt: String,
+    service_id: Option<String>,
+    attachments: Option<Vec<String>>,
+    options: Option<InjectionOptions>,
+) -> Result<InjectionReport, String> {
+    let options = options.unwrap_or_default();
+    inject_prompt_into(&app, &label, &prompt, service_id, &attachments.unwrap_or_default(), &options).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn selectors() -> ServiceSelectors {
+        ServiceSelectors {
+            input_selectors: vec!["textarea[placeholder*='Message']".to_string()],
+            send_selectors: vec!["button[aria-label=\"Send\"]".to_string()],
+            input_strategy: InputStrategy::React,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_selectors_and_strategy_are_passed_as_data() {
+        let args = injection_args("hi", &selectors(), &InjectionOptions::default());
+        assert_eq!(args["prompt"], "hi");
+        assert_eq!(args["mode"], "instant");
+        assert_eq!(args["strategy"], "react");
+        assert_eq!(args["typing"]["newline"], "shift_enter");
+        assert_eq!(args["inputSelectors"][0], "textarea[placeholder*='Message']");
+        assert_eq!(args["sendSelectors"][0], "button[aria-label=\"Send\"]");
+    }
+
+    #[test]
+    fn test_typing_mode_extends_the_timeout() {
+        let typing = TypingOptions::default();
+        let prompt = "x".repeat(1200);
+        assert_eq!(injection_timeout(&prompt, InjectionMode::Instant, &typing), Duration::from_millis(INJECTION_TIMEOUT_MS));
+        // 100 chunks of 12 chars, each up to 30 + 40 + 5 ms
+        assert_eq!(
+            injection_timeout(&prompt, InjectionMode::Typing, &typing),
+            Duration::from_millis(INJECTION_TIMEOUT_MS + 7500)
+        );
+        let args = injection_args("hi", &selectors(), &InjectionOptions { mode: Some(InjectionMode::Typing), strict: true, model: None, fresh_context: false });
+        assert_eq!(args["mode"], "typing");
+        assert_eq!(args["strict"], true);
+    }
+}
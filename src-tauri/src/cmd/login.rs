@@ -0,0 +1,171 @@
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::time::Duration;
+use tauri::{command, Manager};
+use crate::cmd::background;
+use crate::cmd::config::{service_selectors, LoginSelectors};
+use crate::cmd::guard;
+use crate::cmd::runs::RunRegistry;
+use crate::cmd::scripts::run_script;
+use crate::cmd::sessions::WebviewSessions;
+use crate::utils::{log_with_context, log_success, log_warning};
+
+pub const LOGIN_REQUIRED_EVENT: &str = "service://login-required";
+pub const LOGIN_RESTORED_EVENT: &str = "service://login-restored";
+
+const LOGIN_POLL_INTERVAL_MS: u64 = 3000;
+const DEFAULT_LOGIN_WAIT_MS: u64 = 10 * 60 * 1000;
+
+// Checked for every service in addition to its own challengeSelectors
+const DEFAULT_CHALLENGE_SELECTORS: &[&str] = &[
+    "iframe[src*='challenges.cloudflare.com']",
+    "#challenge-form",
+    "#cf-challenge-running",
+    "iframe[src*='recaptcha']",
+    "iframe[src*='hcaptcha']",
+    "[data-testid='arkose-iframe']",
+];
+const CHALLENGE_URL_PATTERNS: &[&str] = &["/cdn-cgi/challenge-platform", "__cf_chl_"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LoginState {
+    LoggedIn,
+    LoggedOut,
+    Challenge,
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LoginStatus {
+    pub label: String,
+    pub service_id: Option<String>,
+    pub state: LoginState,
+    pub url: String,
+    pub matched: Option<String>,
+}
+
+fn classify_url(url: &str, login: &LoginSelectors) -> Option<(LoginState, String)> {
+    if let Some(pattern) = CHALLENGE_URL_PATTERNS.iter().find(|p| url.contains(*p)) {
+        return Some((LoginState::Challenge, pattern.to_string()));
+    }
+    login
+        .login_url_patterns
+        .iter()
+        .find(|p| url.contains(p.as_str()))
+        .map(|p| (LoginState::LoggedOut, p.clone()))
+}
+
+fn login_probe_args(login: &LoginSelectors) -> Value {
+    let mut challenge: Vec<String> = DEFAULT_CHALLENGE_SELECTORS.iter().map(|s| s.to_string()).collect();
+    challenge.extend(login.challenge_selectors.iter().cloned());
+
+    json!({
+        "challengeSelectors": challenge,
+        "loggedOutSelectors": login.logged_out_selectors,
+        "loggedInSelectors": login.logged_in_selectors,
+    })
+}
+
+pub async fn detect_login_state(app: &tauri::AppHandle, label: &str) -> Result<LoginStatus, String> {
+    let window = app
+        .get_window(label)
+        .ok_or_else(|| "Window not found".to_string())?;
+    let service_id = app.state::<WebviewSessions>().get(label).and_then(|s| s.service_id);
+    let login = match service_id.as_deref() {
+        Some(id) => service_selectors(id).map(|s| s.login).unwrap_or_default(),
+        None => LoginSelectors::default(),
+    };
+    let url = window.url().to_string();
+
+    let mut status = LoginStatus {
+        label: label.to_string(),
+        service_id,
+        state: LoginState::Unknown,
+        url: url.clone(),
+        matched: None,
+    };
+
+    // Sign-in pages usually live on other domains without IPC access, so the URL decides first
+    if let Some((state, matched)) = classify_url(&url, &login) {
+        status.state = state;
+        status.matched = Some(matched);
+        return Ok(status);
+    }
+
+    // Pages without IPC access could never answer the probe
+    if !guard::has_ipc_access(app, &window.url()) {
+        return Ok(status);
+    }
+
+    match run_script(&window, "login_probe", &login_probe_args(&login), Duration::from_secs(3)).await {
+        Ok(probe) => {
+            status.state = match probe["state"].as_str() {
+                Some("logged_in") => LoginState::LoggedIn,
+                Some("logged_out") => LoginState::LoggedOut,
+                Some("challenge") => LoginState::Challenge,
+                _ => LoginState::Unknown,
+            };
+            status.matched = probe["matched"].as_str().map(|s| s.to_string());
+        }
+        Err(e) => log_warning("LOGIN", &format!("Login probe failed for {}: {}", label, e)),
+    }
+
+    Ok(status)
+}
+
+fn set_run_status(app: &tauri::AppHandle, label: &str, status: &str) {
+    let run_id = app.state::<WebviewSessions>().get(label).and_then(|s| s.run_id);
+    if let Some(run_id) = run_id {
+        let _ = app.state::<RunRegistry>().update(&run_id, |run| run.status = status.to_string());
+    }
+}
+
+/// Blocks a step until its service window is usable. When the user has to log in
+/// or solve a challenge, the window is brought forward and the run is paused.
+pub async fn ensure_logged_in(app: &tauri::AppHandle, label: &str, timeout_ms: u64) -> Result<LoginStatus, String> {
+    let mut status = detect_login_state(app, label).await?;
+    if !matches!(status.state, LoginState::LoggedOut | LoginState::Challenge) {
+        return Ok(status);
+    }
+
+    log_warning("LOGIN", &format!("{} needs user action: {:?} ({})", label, status.state, status.url));
+    let _ = app.emit_all(LOGIN_REQUIRED_EVENT, status.clone());
+    background::reveal(app, label);
+    set_run_status(app, label, "paused_login");
+
+    let max_iterations = timeout_ms / LOGIN_POLL_INTERVAL_MS;
+    for _ in 0..max_iterations {
+        tokio::time::sleep(Duration::from_millis(LOGIN_POLL_INTERVAL_MS)).await;
+        status = detect_login_state(app, label).await?;
+        if status.state == LoginState::LoggedIn {
+            log_success("LOGIN", &format!("{} is logged in again", label));
+            set_run_status(app, label, "running");
+            background::conceal(app, label);
+            let _ = app.emit_all(LOGIN_RESTORED_EVENT, status.clone());
+            return Ok(status);
+        }
+    }
+
+    Err(format!("Login required for '{}' (still {:?} after {} ms)", label, status.state, timeout_ms))
+}
+
+#[command]
+pub async fn check_login_state(app: tauri::AppHandle, label: String) -> Result<LoginStatus, String> {
+    log_with_context("LOGIN", &format!("Checking login state of {}", label));
+    let status = detect_login_state(&app, &label).await?;
+    if matches!(status.state, LoginState::LoggedOut | LoginState::Challenge) {
+        let _ = app.emit_all(LOGIN_REQUIRED_EVENT, status.clone());
+        background::reveal(&app, &label);
+    }
+    Ok(status)
+}
+
+#[command]
+pub async fn wait_for_login(
+    app: tauri::AppHandle,
+    label: String,
+    timeout_ms: Option<u64>,
+) -> Result<LoginStatus, String> {
+    ensure_logged_in(&app, &label, timeout_ms.unwrap_or(DEFAULT_LOGIN_WAIT_MS)).await
+}
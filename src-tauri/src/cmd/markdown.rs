@@ -0,0 +1,382 @@
+use kuchikiki::{NodeData, NodeRef};
+
+// Never part of an answer
+const SKIPPED: &[&str] = &["script", "style", "noscript", "template", "svg", "button"];
+
+const BLOCKS: &[&str] = &[
+    "address", "article", "aside", "blockquote", "dd", "details", "div", "dl", "dt", "figcaption", "figure",
+    "footer", "h1", "h2", "h3", "h4", "h5", "h6", "header", "hr", "li", "main", "nav", "ol", "p", "pre",
+    "section", "summary", "table", "ul",
+];
+
+// Visual output of MathJax 2, which keeps the source in a sibling <script>
+const MATHJAX2_RENDERED: &[&str] = &["MathJax", "MathJax_Display", "MathJax_Preview", "MathJax_SVG", "MathJax_CHTML"];
+
+/// Converts a parsed response (usually with its chrome already stripped by
+/// `CleanupRules`) into Markdown: fenced code with its language, nested lists,
+/// GFM tables, blockquotes, links, emphasis and formulas as LaTeX.
+pub fn document_to_markdown(document: &NodeRef) -> String {
+    let root = document
+        .select_first("body")
+        .map(|body| body.as_node().clone())
+        .unwrap_or_else(|_| document.clone());
+    blocks(&root, "\n\n").trim().to_string()
+}
+
+pub(crate) fn tag(node: &NodeRef) -> Option<String> {
+    node.as_element().map(|element| element.name.local.to_string())
+}
+
+pub(crate) fn attr(node: &NodeRef, name: &str) -> Option<String> {
+    node.as_element()
+        .and_then(|element| element.attributes.borrow().get(name).map(str::to_string))
+}
+
+fn has_class(node: &NodeRef, class: &str) -> bool {
+    attr(node, "class").is_some_and(|classes| classes.split_whitespace().any(|name| name == class))
+}
+
+fn tex_annotation(node: &NodeRef) -> Option<String> {
+    node.select_first("annotation[encoding='application/x-tex']")
+        .ok()
+        .map(|annotation| annotation.as_node().text_contents())
+}
+
+/// LaTeX source of a rendered formula (KaTeX, MathJax 2/3, Gemini's
+/// data-math, MathML with a TeX annotation) and whether it is display math.
+/// Rendered formulas hold both MathML and visual spans, so their text is
+/// never used.
+fn math_of(node: &NodeRef) -> Option<(String, bool)> {
+    let name = tag(node)?;
+    let (source, display) = if let Some(source) = attr(node, "data-math") {
+        (source, name == "div" || has_class(node, "math-block"))
+    } else if has_class(node, "katex-display") || has_class(node, "katex") {
+        (tex_annotation(node)?, has_class(node, "katex-display"))
+    } else if name == "mjx-container" {
+        let source = tex_annotation(node).or_else(|| {
+            let math = node.select_first("math").ok()?;
+            attr(math.as_node(), "alttext")
+        })?;
+        (source, attr(node, "display").as_deref() == Some("true"))
+    } else if name == "math" {
+        let source = tex_annotation(node).or_else(|| attr(node, "alttext"))?;
+        (source, attr(node, "display").as_deref() == Some("block"))
+    } else if name == "script" {
+        let kind = attr(node, "type")?;
+        if !kind.starts_with("math/tex") {
+            return None;
+        }
+        (node.text_contents(), kind.contains("mode=display"))
+    } else {
+        return None;
+    };
+    let source = source.trim();
+    (!source.is_empty()).then(|| (source.to_string(), display))
+}
+
+fn is_skipped(node: &NodeRef) -> bool {
+    tag(node).is_some_and(|name| SKIPPED.contains(&name.as_str()))
+        || (tag(node).as_deref() != Some("mjx-container")
+            && MATHJAX2_RENDERED.iter().any(|class| has_class(node, class)))
+}
+
+// Custom elements (Gemini's <message-content>, <code-block>) count as blocks
+// when they wrap block content
+fn is_block(node: &NodeRef) -> bool {
+    if let Some((_, display)) = math_of(node) {
+        return display;
+    }
+    match tag(node) {
+        Some(name) if BLOCKS.contains(&name.as_str()) => true,
+        Some(_) if !is_skipped(node) => node.children().any(|child| is_block(&child)),
+        _ => false,
+    }
+}
+
+fn push_paragraph(out: &mut Vec<String>, line: &mut String) {
+    let paragraph = line
+        .split('\n')
+        .map(str::trim)
+        .collect::<Vec<_>>()
+        .join("\n");
+    let paragraph = paragraph.trim();
+    if !paragraph.is_empty() {
+        out.push(paragraph.to_string());
+    }
+    line.clear();
+}
+
+/// Children of `node` as Markdown blocks; inline runs between them become paragraphs.
+fn blocks(node: &NodeRef, separator: &str) -> String {
+    let mut out = Vec::new();
+    let mut line = String::new();
+    for child in node.children() {
+        if is_block(&child) {
+            push_paragraph(&mut out, &mut line);
+            let rendered = block(&child);
+            if !rendered.trim().is_empty() {
+                out.push(rendered);
+            }
+        } else {
+            line.push_str(&inline(&child));
+        }
+    }
+    push_paragraph(&mut out, &mut line);
+    out.join(separator)
+}
+
+fn block(node: &NodeRef) -> String {
+    if let Some((source, _)) = math_of(node) {
+        return format!("$$\n{}\n$$", source);
+    }
+    let name = tag(node).unwrap_or_default();
+    match name.as_str() {
+        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+            let level = name[1..].parse().unwrap_or(1);
+            let text = blocks(node, " ").replace('\n', " ");
+            format!("{} {}", "#".repeat(level), text.trim())
+        }
+        "pre" => code_block(node),
+        "blockquote" => prefix_lines(&blocks(node, "\n\n"), "> ", "> "),
+        "ul" => list(node, false),
+        "ol" => list(node, true),
+        "table" => table(node),
+        "hr" => "---".to_string(),
+        _ => blocks(node, "\n\n"),
+    }
+}
+
+/// Prefixes the first line with `first` and the others with `rest`.
+fn prefix_lines(text: &str, first: &str, rest: &str) -> String {
+    text.split('\n')
+        .enumerate()
+        .map(|(i, line)| {
+            let prefix = if i == 0 { first } else { rest };
+            if line.is_empty() {
+                prefix.trim_end().to_string()
+            } else {
+                format!("{}{}", prefix, line)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn list(node: &NodeRef, ordered: bool) -> String {
+    let start: usize = attr(node, "start").and_then(|start| start.parse().ok()).unwrap_or(1);
+    let mut items = Vec::new();
+    let entries = node.children().filter(|child| tag(child).as_deref() == Some("li"));
+    for (number, item) in (start..).zip(entries) {
+        let marker = if ordered { format!("{}. ", number) } else { "- ".to_string() };
+        // Items stay tight: a nested list follows its item's text directly
+        let body = blocks(&item, "\n");
+        items.push(prefix_lines(&body, &marker, &" ".repeat(marker.len())));
+    }
+    items.join("\n")
+}
+
+pub(crate) fn language_of(node: &NodeRef) -> Option<String> {
+    if let Some(language) = attr(node, "data-language").filter(|language| !language.is_empty()) {
+        return Some(language);
+    }
+    attr(node, "class")?
+        .split_whitespace()
+        .find_map(|class| class.strip_prefix("language-").or_else(|| class.strip_prefix("lang-")))
+        .map(str::to_string)
+}
+
+fn fence_for(content: &str, mark: char, min: usize) -> String {
+    let mut longest = 0;
+    let mut run = 0;
+    for c in content.chars() {
+        run = if c == mark { run + 1 } else { 0 };
+        longest = longest.max(run);
+    }
+    mark.to_string().repeat(min.max(longest + 1))
+}
+
+fn code_block(pre: &NodeRef) -> String {
+    // ChatGPT puts a language header and a copy button next to the <code>
+    let code = pre.select_first("code").ok().map(|code| code.as_node().clone());
+    let source = code.as_ref().unwrap_or(pre);
+    let language = code
+        .as_ref()
+        .and_then(language_of)
+        .or_else(|| language_of(pre))
+        .unwrap_or_default();
+    let content = source.text_contents();
+    let content = content.trim_end_matches('\n');
+    let fence = fence_for(content, '`', 3);
+    format!("{}{}\n{}\n{}", fence, language, content, fence)
+}
+
+fn table(node: &NodeRef) -> String {
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    for row in node.select("tr").into_iter().flatten() {
+        let mut cells = Vec::new();
+        for cell in row.as_node().children() {
+            if !matches!(tag(&cell).as_deref(), Some("td" | "th")) {
+                continue;
+            }
+            let text = blocks(&cell, "\n")
+                .split('\n')
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .collect::<Vec<_>>()
+                .join("<br>")
+                .replace('|', "\\|");
+            let span: usize = attr(&cell, "colspan").and_then(|span| span.parse().ok()).unwrap_or(1);
+            cells.push(text);
+            cells.extend(std::iter::repeat_n(String::new(), span.clamp(1, 100) - 1));
+        }
+        if !cells.is_empty() {
+            rows.push(cells);
+        }
+    }
+    let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+    if width == 0 {
+        return String::new();
+    }
+    let line = |cells: &[String]| {
+        let padded: Vec<&str> = (0..width).map(|i| cells.get(i).map_or("", String::as_str)).collect();
+        format!("| {} |", padded.join(" | "))
+    };
+    let mut out = vec![line(&rows[0]), line(&vec!["---".to_string(); width])];
+    out.extend(rows[1..].iter().map(|row| line(row)));
+    out.join("\n")
+}
+
+fn collapse(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut space = false;
+    for c in text.chars() {
+        if c.is_whitespace() {
+            space = true;
+        } else {
+            if space {
+                out.push(' ');
+                space = false;
+            }
+            out.push(c);
+        }
+    }
+    if space {
+        out.push(' ');
+    }
+    out
+}
+
+fn inline_children(node: &NodeRef) -> String {
+    node.children().map(|child| inline(&child)).collect()
+}
+
+// Keeps the spaces around emphasis outside the markers
+fn wrap(text: &str, mark: &str) -> String {
+    let core = text.trim();
+    if core.is_empty() {
+        return text.to_string();
+    }
+    let lead = if text.starts_with(' ') { " " } else { "" };
+    let trail = if text.ends_with(' ') { " " } else { "" };
+    format!("{}{}{}{}{}", lead, mark, core, mark, trail)
+}
+
+fn inline(node: &NodeRef) -> String {
+    if let Some((source, display)) = math_of(node) {
+        // Display math within inline content still gets lines of its own
+        return if display { format!("\n$$\n{}\n$$\n", source) } else { format!("${}$", source) };
+    }
+    match node.data() {
+        NodeData::Text(text) => collapse(&text.borrow()),
+        NodeData::Element(_) if is_skipped(node) => String::new(),
+        NodeData::Element(_) => match tag(node).unwrap_or_default().as_str() {
+            "br" => "\n".to_string(),
+            "strong" | "b" => wrap(&inline_children(node), "**"),
+            "em" | "i" => wrap(&inline_children(node), "*"),
+            "del" | "s" | "strike" => wrap(&inline_children(node), "~~"),
+            "code" => {
+                let code = node.text_contents();
+                let fence = fence_for(&code, '`', 1);
+                let pad = if code.starts_with('`') || code.ends_with('`') { " " } else { "" };
+                format!("{}{}{}{}{}", fence, pad, code, pad, fence)
+            }
+            "a" => {
+                let text = inline_children(node);
+                match attr(node, "href").filter(|href| !href.is_empty() && !href.starts_with("javascript:")) {
+                    Some(href) if !text.trim().is_empty() => format!("[{}]({})", text.trim(), href),
+                    _ => text,
+                }
+            }
+            "img" => match attr(node, "src") {
+                Some(src) => format!("![{}]({})", attr(node, "alt").unwrap_or_default(), src),
+                None => String::new(),
+            },
+            _ => inline_children(node),
+        },
+        _ => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kuchikiki::traits::TendrilSink;
+
+    fn html_to_markdown(html: &str) -> String {
+        document_to_markdown(&kuchikiki::parse_html().one(html))
+    }
+
+    #[test]
+    fn test_code_blocks_keep_language_and_content() {
+        let html = r#"<p>Run:</p><pre><div>python<button>Copy code</button></div><code class="hljs language-python">def f():
+    return "```"
+</code></pre>"#;
+        assert_eq!(
+            html_to_markdown(html),
+            "Run:\n\n````python\ndef f():\n    return \"```\"\n````"
+        );
+        assert_eq!(html_to_markdown("<p>Use <code>cargo test</code> here</p>"), "Use `cargo test` here");
+    }
+
+    #[test]
+    fn test_nested_lists_and_quotes() {
+        let html = "<ol start=\"3\"><li>First<ul><li>inner <strong>bold</strong></li></ul></li><li><p>Second</p></li></ol>\
+                    <blockquote><p>Quoted</p><p>See <a href=\"https://example.com\">this</a></p></blockquote>";
+        assert_eq!(
+            html_to_markdown(html),
+            "3. First\n   - inner **bold**\n4. Second\n\n> Quoted\n>\n> See [this](https://example.com)"
+        );
+    }
+
+    #[test]
+    fn test_tables_become_gfm() {
+        let html = "<h2>Results</h2><table><thead><tr><th>Name</th><th>Value</th></tr></thead>\
+                    <tbody><tr><td>a|b</td><td>1</td></tr><tr><td colspan=\"2\">total</td></tr></tbody></table>";
+        assert_eq!(
+            html_to_markdown(html),
+            "## Results\n\n| Name | Value |\n| --- | --- |\n| a\\|b | 1 |\n| total |  |"
+        );
+    }
+
+    #[test]
+    fn test_formulas_keep_their_latex() {
+        let katex = |source: &str, display: bool| {
+            let inner = format!(
+                r#"<span class="katex"><span class="katex-mathml"><math><semantics><mrow><mi>x</mi></mrow><annotation encoding="application/x-tex">{}</annotation></semantics></math></span><span class="katex-html" aria-hidden="true"><span class="mord">x²</span></span></span>"#,
+                source
+            );
+            if display { format!(r#"<span class="katex-display">{}</span>"#, inner) } else { inner }
+        };
+        let html = format!(
+            "<p>Energy {} where</p><p>{}</p><div class=\"math-block\" data-math=\"\\int_0^1 f\"><span>∫f</span></div>\
+             <p>MathJax <mjx-container class=\"MathJax\" display=\"true\"><mjx-math>a</mjx-math><mjx-assistive-mml><math alttext=\"a^2\"><mi>a</mi></math></mjx-assistive-mml></mjx-container>\
+             and <span class=\"MathJax_Preview\">b2</span><span class=\"MathJax\">b2</span><script type=\"math/tex\">b^2</script></p>",
+            katex("E = mc^2", false),
+            katex(r"\frac{a}{b}", true)
+        );
+        assert_eq!(
+            html_to_markdown(&html),
+            "Energy $E = mc^2$ where\n\n$$\n\\frac{a}{b}\n$$\n\n$$\n\\int_0^1 f\n$$\n\nMathJax\n\n$$\na^2\n$$\n\nand $b^2$"
+        );
+    }
+}
@@ -0,0 +1,241 @@
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::time::Duration;
+use tauri::{command, Manager};
+use crate::cmd::attachments::mime_type_for;
+use crate::cmd::config::service_selectors;
+use crate::cmd::runs::{run_directory, RunRegistry};
+use crate::cmd::scripts::run_script;
+use crate::utils::{log_with_context, log_warning};
+
+const MEDIA_TIMEOUT_MS: u64 = 120000;
+const MAX_MEDIA_BYTES: u64 = 25 * 1024 * 1024;
+
+// Extensions given to saved files, looked up by content type
+const EXTENSIONS: &[&str] = &[
+    "png", "jpg", "gif", "webp", "svg", "pdf", "zip", "docx", "xlsx", "pptx", "mp3", "mp4", "json", "csv", "md",
+    "html", "xml", "txt",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MediaKind {
+    Image,
+    File,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct RawMedia {
+    kind: MediaKind,
+    url: String,
+    name: Option<String>,
+    alt: Option<String>,
+    content_type: Option<String>,
+    // Base64, missing when the fetch failed
+    data: Option<String>,
+    error: Option<String>,
+}
+
+/// An image or file of an answer, saved in the run's artifact folder.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MediaItem {
+    pub kind: MediaKind,
+    // Where the page had it (https:, blob: or data: URL)
+    pub url: String,
+    // Saved file, none when it could not be fetched
+    pub path: Option<String>,
+    pub content_type: String,
+    pub bytes: usize,
+    #[serde(default)]
+    pub alt: Option<String>,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// Content type from the file's leading bytes.
+pub fn sniff_content_type(bytes: &[u8]) -> Option<&'static str> {
+    let head = &bytes[..bytes.len().min(512)];
+    let text = String::from_utf8_lossy(head);
+    let text = text.trim_start();
+    match head {
+        [0x89, b'P', b'N', b'G', ..] => Some("image/png"),
+        [0xFF, 0xD8, 0xFF, ..] => Some("image/jpeg"),
+        [b'G', b'I', b'F', b'8', ..] => Some("image/gif"),
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => Some("image/webp"),
+        [b'%', b'P', b'D', b'F', ..] => Some("application/pdf"),
+        [b'P', b'K', 3, 4, ..] => Some("application/zip"),
+        [b'I', b'D', b'3', ..] => Some("audio/mpeg"),
+        [_, _, _, _, b'f', b't', b'y', b'p', ..] => Some("video/mp4"),
+        _ if text.starts_with("<svg") || (text.starts_with("<?xml") && text.contains("<svg")) => Some("image/svg+xml"),
+        _ => None,
+    }
+}
+
+/// The sniffed type, unless the declared one is a more specific kind of the
+/// same container (an .xlsx is a zip archive).
+pub fn detect_content_type(bytes: &[u8], declared: Option<&str>) -> String {
+    let declared = declared
+        .and_then(|declared| declared.split(';').next())
+        .map(|declared| declared.trim().to_lowercase())
+        .filter(|declared| !declared.is_empty());
+    match (sniff_content_type(bytes), declared) {
+        (Some("application/zip"), Some(declared)) if declared.contains("openxmlformats") || declared.ends_with("+zip") => {
+            declared
+        }
+        (Some(sniffed), _) => sniffed.to_string(),
+        (None, Some(declared)) => declared,
+        (None, None) => "application/octet-stream".to_string(),
+    }
+}
+
+fn extension_for(content_type: &str) -> &'static str {
+    EXTENSIONS
+        .iter()
+        .find(|extension| mime_type_for(extension) == content_type)
+        .copied()
+        .unwrap_or("bin")
+}
+
+/// `<step>-<n>-<name>` with the page's name (or the URL's last segment)
+/// reduced to safe characters and an extension matching the content.
+pub fn media_filename(name: Option<&str>, url: &str, content_type: &str, step: usize, number: usize) -> String {
+    let from_url = url
+        .split(['?', '#'])
+        .next()
+        .filter(|_| url.starts_with("http"))
+        .and_then(|path| path.rsplit('/').next());
+    let stem: String = name
+        .or(from_url)
+        .unwrap_or_default()
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .map(|c| if c.is_alphanumeric() || matches!(c, '.' | '-' | '_') { c } else { '-' })
+        .collect();
+    let stem = stem.trim_matches(['-', '.']);
+    let stem = if stem.is_empty() { "media" } else { stem };
+    let extension = extension_for(content_type);
+    // A known extension that contradicts the content is replaced
+    let (base, current) = match stem.rsplit_once('.') {
+        Some((base, current)) if !base.is_empty() => (base, Some(current.to_lowercase())),
+        _ => (stem, None),
+    };
+    match current {
+        Some(current) if mime_type_for(&current) == content_type || current == extension => {
+            format!("{}-{}-{}", step, number, stem)
+        }
+        Some(current) if mime_type_for(&current) != "application/octet-stream" => {
+            format!("{}-{}-{}.{}", step, number, base, extension)
+        }
+        _ => format!("{}-{}-{}.{}", step, number, stem, extension),
+    }
+}
+
+/// Fetches the step's images and downloadable files through its window,
+/// saves them in the run's artifact folder and lists them on the step.
+#[command]
+pub async fn download_response_media(
+    app: tauri::AppHandle,
+    runs: tauri::State<'_, RunRegistry>,
+    run_id: String,
+    step_index: usize,
+) -> Result<Vec<MediaItem>, String> {
+    let run = runs.get(&run_id).ok_or_else(|| format!("Run '{}' not found", run_id))?;
+    let step = run
+        .steps
+        .iter()
+        .find(|step| step.index == step_index)
+        .ok_or_else(|| format!("Step {} not found in run '{}'", step_index, run_id))?;
+    let window = app
+        .get_window(&step.label)
+        .ok_or_else(|| "Window not found".to_string())?;
+    let selectors = service_selectors(&step.service_id)?;
+
+    let args = json!({
+        "responseSelectors": selectors.response_selectors,
+        "excludeSelectors": selectors.cleanup.exclude_selectors,
+        "maxBytes": MAX_MEDIA_BYTES,
+    });
+    let value = run_script(&window, "collect_media", &args, Duration::from_millis(MEDIA_TIMEOUT_MS)).await?;
+    let found: Vec<RawMedia> = serde_json::from_value(value).map_err(|e| format!("Invalid media list: {}", e))?;
+
+    let directory = run_directory(&run_id)?.join("artifacts");
+    let mut items = Vec::new();
+    for (number, raw) in (1..).zip(found) {
+        let data = raw
+            .data
+            .as_deref()
+            .ok_or_else(|| raw.error.clone().unwrap_or_else(|| "no data".to_string()))
+            .and_then(|data| base64::engine::general_purpose::STANDARD.decode(data).map_err(|e| e.to_string()));
+        let item = match data {
+            Ok(bytes) => {
+                let content_type = detect_content_type(&bytes, raw.content_type.as_deref());
+                let path = directory.join(media_filename(raw.name.as_deref(), &raw.url, &content_type, step_index, number));
+                std::fs::create_dir_all(&directory)
+                    .and_then(|_| std::fs::write(&path, &bytes))
+                    .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+                MediaItem {
+                    kind: raw.kind,
+                    url: raw.url,
+                    path: Some(path.to_string_lossy().to_string()),
+                    content_type,
+                    bytes: bytes.len(),
+                    alt: raw.alt,
+                    error: None,
+                }
+            }
+            Err(error) => {
+                log_warning("MEDIA", &format!("{}: could not fetch {}: {}", step.label, raw.url, error));
+                MediaItem {
+                    kind: raw.kind,
+                    url: raw.url,
+                    path: None,
+                    content_type: raw.content_type.unwrap_or_default(),
+                    bytes: 0,
+                    alt: raw.alt,
+                    error: Some(error),
+                }
+            }
+        };
+        items.push(item);
+    }
+
+    let saved = items.iter().filter(|item| item.path.is_some()).count();
+    log_with_context("MEDIA", &format!("{}: step {} saved {} of {} media file(s)", run_id, step_index, saved, items.len()));
+    runs.update(&run_id, |run| {
+        if let Some(step) = run.steps.iter_mut().find(|step| step.index == step_index) {
+            step.media = items.clone();
+        }
+    })?;
+    Ok(items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_content_type_prefers_the_bytes() {
+        assert_eq!(detect_content_type(b"\x89PNG\r\n\x1a\n....", Some("application/octet-stream")), "image/png");
+        assert_eq!(
+            detect_content_type(b"PK\x03\x04....", Some("application/vnd.openxmlformats-officedocument.spreadsheetml.sheet")),
+            "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet"
+        );
+        assert_eq!(detect_content_type(b"  <?xml version=\"1.0\"?><svg></svg>", None), "image/svg+xml");
+        assert_eq!(detect_content_type(b"a,b\n1,2\n", Some("text/csv; charset=utf-8")), "text/csv");
+        assert_eq!(detect_content_type(b"???", None), "application/octet-stream");
+    }
+
+    #[test]
+    fn test_filenames_are_safe_and_typed() {
+        assert_eq!(
+            media_filename(None, "https://files.example/img/chart%201.webp?sig=x", "image/png", 2, 1),
+            "2-1-chart-201.png"
+        );
+        assert_eq!(media_filename(Some("../report.xlsx"), "blob:https://chatgpt.com/1", mime_type_for("xlsx"), 0, 3), "0-3-report.xlsx");
+        assert_eq!(media_filename(Some("photo.JPG"), "data:image/jpeg;base64,", "image/jpeg", 1, 2), "1-2-photo.JPG");
+        assert_eq!(media_filename(None, "blob:https://gemini.google.com/abc", "image/jpeg", 1, 1), "1-1-media.jpg");
+    }
+}
@@ -8,3 +8,29 @@ pub mod history;
 pub mod drop;
 pub mod logging;
 pub mod config;
+pub mod profiles;
+pub mod runs;
+pub mod sessions;
+pub mod bridge;
+pub mod login;
+pub mod background;
+pub mod guard;
+pub mod console;
+pub mod watchdog;
+pub mod snapshot;
+pub mod injection;
+pub mod scripts;
+pub mod attachments;
+pub mod continuation;
+pub mod service_errors;
+pub mod models;
+pub mod conversation;
+pub mod markdown;
+pub mod sources;
+pub mod extraction;
+pub mod artifacts;
+pub mod completion;
+pub mod cleanup;
+pub mod tables;
+pub mod media;
+pub mod provenance;
@@ -0,0 +1,192 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::time::Duration;
+use tauri::{command, Manager};
+use crate::cmd::config::{service_selectors, ModelPicker};
+use crate::cmd::runs::RunRegistry;
+use crate::cmd::scripts::run_script;
+use crate::cmd::sessions::WebviewSessions;
+use crate::utils::{log_with_context, log_success, log_warning};
+
+const PICKER_TIMEOUT_MS: u64 = 15000;
+// Menus animate open and the header updates after the request round trip
+const MENU_SETTLE_MS: u64 = 800;
+
+#[derive(Debug, Clone, Deserialize)]
+struct PickerResult {
+    active: String,
+    option: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelSelection {
+    pub requested: String,
+    // Text of the active-model element after switching
+    pub active: String,
+    pub switched: bool,
+    pub verified: bool,
+}
+
+/// Splits a chain step such as `chatgpt:o1` into service and model.
+pub fn parse_chain_step(step: &str) -> (&str, Option<&str>) {
+    match step.split_once(':') {
+        Some((service, model)) if !model.trim().is_empty() => (service.trim(), Some(model.trim())),
+        Some((service, _)) => (service.trim(), None),
+        None => (step.trim(), None),
+    }
+}
+
+/// Labels the model may appear under; the model id itself when not configured.
+pub fn option_labels(picker: &ModelPicker, model: &str) -> Vec<String> {
+    picker
+        .models
+        .iter()
+        .find(|(id, _)| id.eq_ignore_ascii_case(model))
+        .map(|(_, labels)| labels.clone())
+        .filter(|labels| !labels.is_empty())
+        .unwrap_or_else(|| vec![model.to_string()])
+}
+
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric() && c != '.')
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Whether the active-model text names one of `labels`. The label has to end
+/// the text, so "4o" does not match "ChatGPT 4o mini".
+pub fn is_active(active: &str, labels: &[String]) -> bool {
+    let first_line = words(active.lines().next().unwrap_or_default());
+    labels
+        .iter()
+        .map(|label| words(label))
+        .any(|label| !label.is_empty() && first_line.ends_with(&label))
+}
+
+async fn run_picker(window: &tauri::Window, picker: &ModelPicker, labels: &[String], open: bool) -> Result<PickerResult, String> {
+    let args = json!({
+        "menuSelectors": picker.menu_selectors,
+        "optionSelectors": picker.option_selectors,
+        "activeSelectors": picker.active_selectors,
+        "labels": labels,
+        "open": open,
+        "settleMs": MENU_SETTLE_MS,
+    });
+    let value = run_script(window, "model_picker", &args, Duration::from_millis(PICKER_TIMEOUT_MS)).await?;
+    serde_json::from_value(value).map_err(|e| format!("Invalid model picker result: {}", e))
+}
+
+/// The model the window shows as active, read without opening the picker.
+pub async fn active_model(window: &tauri::Window, picker: &ModelPicker) -> Option<String> {
+    if picker.active_selectors.is_empty() {
+        return None;
+    }
+    match run_picker(window, picker, &[], false).await {
+        Ok(result) => Some(result.active.trim().to_string()).filter(|active| !active.is_empty()),
+        Err(e) => {
+            log_warning("MODEL", &format!("{}: could not read the active model: {}", window.label(), e));
+            None
+        }
+    }
+}
+
+/// Switches the service window to `model` through its model picker unless it
+/// is already active, and records the model that ended up active on the run.
+pub async fn select_model_in(
+    app: &tauri::AppHandle,
+    label: &str,
+    service_id: &str,
+    model: &str,
+) -> Result<ModelSelection, String> {
+    let window = app
+        .get_window(label)
+        .ok_or_else(|| "Window not found".to_string())?;
+    let picker = service_selectors(service_id)?.model_picker;
+    if picker.menu_selectors.is_empty() {
+        return Err(format!("No modelPicker configured for {}", service_id));
+    }
+    let labels = option_labels(&picker, model);
+
+    let current = run_picker(&window, &picker, &labels, false).await?;
+    let mut selection = ModelSelection {
+        requested: model.to_string(),
+        active: current.active.trim().to_string(),
+        switched: false,
+        verified: is_active(&current.active, &labels),
+    };
+    if !selection.verified {
+        log_with_context("MODEL", &format!("{}: switching from '{}' to {}", label, selection.active, model));
+        let after = run_picker(&window, &picker, &labels, true).await?;
+        selection.switched = after.option.is_some();
+        selection.active = after.active.trim().to_string();
+        selection.verified = is_active(&after.active, &labels);
+    }
+
+    let run_id = app.state::<WebviewSessions>().get(label).and_then(|s| s.run_id);
+    if selection.verified {
+        log_success("MODEL", &format!("{}: {} active ('{}')", label, model, selection.active));
+    } else {
+        let note = format!("{}: requested model {} but '{}' is active", label, model, selection.active);
+        log_warning("MODEL", &note);
+        if let Some(run_id) = run_id.as_deref() {
+            let _ = app.state::<RunRegistry>().update(run_id, |run| run.warnings.push(note));
+        }
+    }
+    if let Some(run_id) = run_id.as_deref() {
+        let active = selection.active.clone();
+        let _ = app
+            .state::<RunRegistry>()
+            .update(run_id, |run| {
+                run.models.insert(service_id.to_string(), active);
+            });
+    }
+    Ok(selection)
+}
+
+#[command]
+pub async fn select_model(
+    app: tauri::AppHandle,
+    label: String,
+    model: String,
+    service_id: Option<String>,
+) -> Result<ModelSelection, String> {
+    let service_id = service_id
+        .or_else(|| app.state::<WebviewSessions>().get(&label).and_then(|s| s.service_id))
+        .ok_or_else(|| format!("No service known for window '{}'", label))?;
+    select_model_in(&app, &label, &service_id, &model).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn labels(items: &[&str]) -> Vec<String> {
+        items.iter().map(|item| item.to_string()).collect()
+    }
+
+    #[test]
+    fn test_chain_steps_with_models() {
+        assert_eq!(parse_chain_step("chatgpt:o1"), ("chatgpt", Some("o1")));
+        assert_eq!(parse_chain_step("gemini: pro "), ("gemini", Some("pro")));
+        assert_eq!(parse_chain_step("claude"), ("claude", None));
+        assert_eq!(parse_chain_step("claude:"), ("claude", None));
+    }
+
+    #[test]
+    fn test_active_model_matches_label_at_the_end() {
+        assert!(is_active("ChatGPT o1", &labels(&["o1"])));
+        assert!(is_active("Gemini 2.5 Pro\nOur most capable", &labels(&["2.5 Pro"])));
+        assert!(!is_active("ChatGPT 4o mini", &labels(&["GPT-4o", "4o"])));
+        assert!(is_active("ChatGPT 4o mini", &labels(&["4o mini"])));
+        assert!(!is_active("", &labels(&["o1"])));
+    }
+
+    #[test]
+    fn test_labels_fall_back_to_the_model_id() {
+        let mut picker = ModelPicker::default();
+        picker.models.insert("Pro".to_string(), labels(&["2.5 Pro"]));
+        assert_eq!(option_labels(&picker, "pro"), labels(&["2.5 Pro"]));
+        assert_eq!(option_labels(&picker, "o3"), labels(&["o3"]));
+    }
+}
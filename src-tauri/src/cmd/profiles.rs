@@ -0,0 +1,246 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use tauri::command;
+use crate::utils::{get_app_data_directory, log_with_context, log_error, log_success};
+
+// Name reserved for the shared WebView data store (no separate directory)
+pub const DEFAULT_PROFILE: &str = "default";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BrowserProfile {
+    pub name: String,
+    pub description: Option<String>,
+    pub user_agent: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ProfileIndex {
+    pub profiles: Vec<BrowserProfile>,
+    // service id -> profile name used when a run does not pick one
+    pub service_defaults: HashMap<String, String>,
+}
+
+fn profiles_root() -> Result<PathBuf, String> {
+    get_app_data_directory()
+        .map(|dir| dir.join("profiles"))
+        .map_err(|e| format!("Failed to get app data directory: {}", e))
+}
+
+fn index_path() -> Result<PathBuf, String> {
+    Ok(profiles_root()?.join("profiles.json"))
+}
+
+pub fn load_profile_index() -> Result<ProfileIndex, String> {
+    let path = index_path()?;
+    if !path.exists() {
+        return Ok(ProfileIndex::default());
+    }
+
+    let content = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read profile index: {}", e))?;
+    serde_json::from_str(&content)
+        .map_err(|e| format!("Invalid profile index: {}", e))
+}
+
+fn save_profile_index(index: &ProfileIndex) -> Result<(), String> {
+    let root = profiles_root()?;
+    std::fs::create_dir_all(&root)
+        .map_err(|e| format!("Failed to create profiles directory: {}", e))?;
+
+    let content = serde_json::to_string_pretty(index).map_err(|e| e.to_string())?;
+    std::fs::write(root.join("profiles.json"), content)
+        .map_err(|e| format!("Failed to save profile index: {}", e))
+}
+
+fn validate_profile_name(name: &str) -> Result<(), String> {
+    let valid = !name.is_empty()
+        && name.len() <= 64
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid profile name '{}': use 1-64 letters, digits, '-' or '_'", name))
+    }
+}
+
+/// WebView data directory for a profile, `None` for the shared default store.
+pub fn profile_data_directory(name: &str) -> Result<Option<PathBuf>, String> {
+    if name == DEFAULT_PROFILE {
+        return Ok(None);
+    }
+    validate_profile_name(name)?;
+    Ok(Some(profiles_root()?.join(name).join("webview")))
+}
+
+pub fn find_profile(name: &str) -> Result<Option<BrowserProfile>, String> {
+    if name == DEFAULT_PROFILE {
+        return Ok(None);
+    }
+    let index = load_profile_index()?;
+    match index.profiles.into_iter().find(|p| p.name == name) {
+        Some(profile) => Ok(Some(profile)),
+        None => Err(format!("Profile '{}' does not exist", name)),
+    }
+}
+
+/// Picks the profile for a service: explicit choice, then the run's choice,
+/// then the service default, then the shared store.
+fn choose_profile(
+    service_id: Option<&str>,
+    explicit: Option<&str>,
+    run_profiles: Option<&HashMap<String, String>>,
+    service_defaults: &HashMap<String, String>,
+) -> String {
+    explicit
+        .map(str::to_string)
+        .or_else(|| {
+            let service_id = service_id?;
+            run_profiles
+                .and_then(|profiles| profiles.get(service_id))
+                .or_else(|| service_defaults.get(service_id))
+                .cloned()
+        })
+        .unwrap_or_else(|| DEFAULT_PROFILE.to_string())
+}
+
+/// `choose_profile` with the service defaults from the profile index, read
+/// only when neither the caller nor the run picked a profile.
+pub fn resolve_profile(
+    service_id: Option<&str>,
+    explicit: Option<&str>,
+    run_profiles: Option<&HashMap<String, String>>,
+) -> Result<String, String> {
+    let service_defaults = match (explicit, service_id) {
+        (None, Some(id)) if !run_profiles.is_some_and(|profiles| profiles.contains_key(id)) => {
+            load_profile_index()?.service_defaults
+        }
+        _ => HashMap::new(),
+    };
+    Ok(choose_profile(service_id, explicit, run_profiles, &service_defaults))
+}
+
+#[command]
+pub async fn list_profiles() -> Result<ProfileIndex, String> {
+    load_profile_index()
+}
+
+#[command]
+pub async fn create_profile(
+    name: String,
+    description: Option<String>,
+    user_agent: Option<String>,
+) -> Result<BrowserProfile, String> {
+    log_with_context("PROFILES", &format!("Creating profile: {}", name));
+    validate_profile_name(&name)?;
+    if name == DEFAULT_PROFILE {
+        return Err(format!("'{}' is reserved for the shared data store", DEFAULT_PROFILE));
+    }
+
+    let mut index = load_profile_index()?;
+    if index.profiles.iter().any(|p| p.name == name) {
+        return Err(format!("Profile '{}' already exists", name));
+    }
+
+    if let Some(data_dir) = profile_data_directory(&name)? {
+        std::fs::create_dir_all(&data_dir)
+            .map_err(|e| format!("Failed to create profile directory: {}", e))?;
+    }
+
+    let profile = BrowserProfile {
+        name,
+        description,
+        user_agent,
+        created_at: chrono::Utc::now().to_rfc3339(),
+    };
+    index.profiles.push(profile.clone());
+    save_profile_index(&index)?;
+
+    log_success("PROFILES", &format!("Profile '{}' created", profile.name));
+    Ok(profile)
+}
+
+#[command]
+pub async fn delete_profile(
+    sessions: tauri::State<'_, crate::cmd::sessions::WebviewSessions>,
+    name: String,
+) -> Result<String, String> {
+    log_with_context("PROFILES", &format!("Deleting profile: {}", name));
+    validate_profile_name(&name)?;
+
+    if let Some(label) = sessions.labels_using_profile(&name).first() {
+        return Err(format!("Profile '{}' is in use by window '{}'", name, label));
+    }
+
+    let mut index = load_profile_index()?;
+    let before = index.profiles.len();
+    index.profiles.retain(|p| p.name != name);
+    if index.profiles.len() == before {
+        return Err(format!("Profile '{}' does not exist", name));
+    }
+    index.service_defaults.retain(|_, profile| profile != &name);
+    save_profile_index(&index)?;
+
+    let profile_dir = profiles_root()?.join(&name);
+    if profile_dir.exists() {
+        if let Err(e) = std::fs::remove_dir_all(&profile_dir) {
+            log_error("PROFILES", &format!("Failed to remove {:?}: {}", profile_dir, e));
+        }
+    }
+
+    log_success("PROFILES", &format!("Profile '{}' deleted", name));
+    Ok(format!("Profile '{}' deleted", name))
+}
+
+#[command]
+pub async fn set_service_profile(
+    service_id: String,
+    profile: Option<String>,
+) -> Result<String, String> {
+    let mut index = load_profile_index()?;
+
+    match profile {
+        Some(name) if name != DEFAULT_PROFILE => {
+            if !index.profiles.iter().any(|p| p.name == name) {
+                return Err(format!("Profile '{}' does not exist", name));
+            }
+            index.service_defaults.insert(service_id.clone(), name.clone());
+            save_profile_index(&index)?;
+            Ok(format!("{} now uses profile '{}'", service_id, name))
+        }
+        _ => {
+            index.service_defaults.remove(&service_id);
+            save_profile_index(&index)?;
+            Ok(format!("{} now uses the default profile", service_id))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_names_are_path_safe() {
+        assert!(validate_profile_name("work-2_a").is_ok());
+        assert!(validate_profile_name(&"a".repeat(64)).is_ok());
+        assert!(validate_profile_name("").is_err());
+        assert!(validate_profile_name(&"a".repeat(65)).is_err());
+        assert!(validate_profile_name("../other").is_err());
+        assert!(validate_profile_name("with space").is_err());
+    }
+
+    #[test]
+    fn test_profile_precedence() {
+        let run: HashMap<String, String> = [("claude".to_string(), "run".to_string())].into();
+        let defaults: HashMap<String, String> =
+            [("claude".to_string(), "service".to_string()), ("gemini".to_string(), "service".to_string())].into();
+
+        assert_eq!(choose_profile(Some("claude"), Some("explicit"), Some(&run), &defaults), "explicit");
+        assert_eq!(choose_profile(Some("claude"), None, Some(&run), &defaults), "run");
+        assert_eq!(choose_profile(Some("gemini"), None, Some(&run), &defaults), "service");
+        assert_eq!(choose_profile(Some("chatgpt"), None, Some(&run), &defaults), DEFAULT_PROFILE);
+        assert_eq!(choose_profile(None, None, Some(&run), &defaults), DEFAULT_PROFILE);
+    }
+}
@@ -0,0 +1,112 @@
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+use crate::cmd::runs::RunRegistry;
+use crate::cmd::sessions::WebviewSessions;
+
+/// How an answer was obtained, stored next to it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Provenance {
+    pub service_id: String,
+    // Model shown as active when the prompt was sent
+    #[serde(default)]
+    pub model: Option<String>,
+    pub label: String,
+    #[serde(default)]
+    pub profile: Option<String>,
+    #[serde(default)]
+    pub page_url: Option<String>,
+    // Response selector that matched
+    #[serde(default)]
+    pub selector: Option<String>,
+    // "dom_selector_<n>", "conversation_turns", ... as reported by the extraction
+    pub extraction_method: String,
+    #[serde(default)]
+    pub completion_reason: Option<String>,
+    // From the start of the wait for the answer to its extraction
+    pub started_at: String,
+    pub finished_at: String,
+    // Share of the answer quality checks passed, 0 to 1
+    pub quality_score: f64,
+}
+
+/// Runs of `.`, `!` and `?`, counted once like the page-side extractor does.
+fn sentence_marks(text: &str) -> usize {
+    let mut count = 0;
+    let mut previous = ' ';
+    for c in text.chars() {
+        if matches!(c, '.' | '!' | '?') && !matches!(previous, '.' | '!' | '?') {
+            count += 1;
+        }
+        previous = c;
+    }
+    count
+}
+
+/// The extract_monitored checks (length, structure, variety, completeness,
+/// coherence) as the share of them the answer passes.
+pub fn quality_score(answer: &str) -> f64 {
+    let answer = answer.trim();
+    let length = answer.chars().count();
+    let words = answer.split_whitespace().count();
+    let sentences = sentence_marks(answer);
+    let paragraphs = answer.split("\n\n").filter(|paragraph| !paragraph.trim().is_empty()).count();
+    let has_list = answer.lines().any(|line| {
+        let line = line.trim_start();
+        ["- ", "* ", "+ ", "• "].iter().any(|marker| line.starts_with(marker))
+    });
+    let checks = [
+        (30..=50000).contains(&length),
+        sentences >= 2 || paragraphs >= 2,
+        answer.contains("```") || has_list || answer.chars().any(|c| c.is_ascii_digit()),
+        !answer.ends_with("...") && !answer.to_lowercase().contains("loading"),
+        words >= 10 && sentences >= 1,
+    ];
+    checks.iter().filter(|passed| **passed).count() as f64 / checks.len() as f64
+}
+
+/// Provenance of an answer just read from a window: session, run and
+/// completion details are filled in from what the backend recorded.
+pub fn collect(
+    app: &tauri::AppHandle,
+    label: &str,
+    service_id: &str,
+    extraction_method: &str,
+    selector: Option<String>,
+    answer: &str,
+) -> Provenance {
+    let session = app.state::<WebviewSessions>().get(label);
+    let model = session.as_ref().and_then(|session| session.active_model.clone()).or_else(|| {
+        session
+            .as_ref()
+            .and_then(|session| session.run_id.as_deref())
+            .and_then(|run_id| app.state::<RunRegistry>().get(run_id))
+            .and_then(|run| run.models.get(service_id).cloned())
+    });
+    let now = chrono::Utc::now().to_rfc3339();
+    Provenance {
+        service_id: service_id.to_string(),
+        model,
+        label: label.to_string(),
+        profile: session.as_ref().map(|session| session.profile.clone()),
+        page_url: app.get_window(label).map(|window| window.url().to_string()),
+        selector,
+        extraction_method: extraction_method.to_string(),
+        completion_reason: session.as_ref().and_then(|session| session.completion_reason.clone()),
+        started_at: session.and_then(|session| session.answer_started_at).unwrap_or_else(|| now.clone()),
+        finished_at: now,
+        quality_score: quality_score(answer),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quality_score_counts_passed_checks() {
+        let answer = "Rust has 3 main features.\n\n- Ownership\n- Borrowing\n- Lifetimes\n\nEach one prevents a class of bugs.";
+        assert_eq!(quality_score(answer), 1.0);
+        assert_eq!(quality_score("Loading..."), 0.0);
+        assert_eq!(quality_score("Yes!!! It works, mostly fine for me today"), 0.4);
+    }
+}
@@ -0,0 +1,277 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{command, Manager};
+use crate::cmd::artifacts::{extract_artifacts, CodeArtifact};
+use crate::cmd::attachments::AttachmentRecord;
+use crate::cmd::background::{self, WindowMode};
+use crate::cmd::media::MediaItem;
+use crate::cmd::models::parse_chain_step;
+use crate::cmd::provenance::{self, Provenance};
+use crate::cmd::sessions::WebviewSessions;
+use crate::cmd::sources::Source;
+use crate::cmd::tables::Table;
+use crate::utils::{get_app_data_directory, log_with_context, log_error};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RunMetadata {
+    pub run_id: String,
+    pub chain: Vec<String>,
+    pub status: String,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+    // service id -> profile actually used by that service's window
+    pub profiles: HashMap<String, String>,
+    #[serde(default)]
+    pub window_mode: WindowMode,
+    #[serde(default)]
+    pub service_window_modes: HashMap<String, WindowMode>,
+    #[serde(default)]
+    pub warnings: Vec<String>,
+    // Incident folders saved when a step failed
+    #[serde(default)]
+    pub incidents: Vec<String>,
+    // service id -> files attached to that service's prompt
+    #[serde(default)]
+    pub attachments: HashMap<String, Vec<AttachmentRecord>>,
+    // service id -> model shown as active when its prompt was sent
+    #[serde(default)]
+    pub models: HashMap<String, String>,
+    #[serde(default)]
+    pub steps: Vec<StepResult>,
+}
+
+impl RunMetadata {
+    /// Records the profile a service's window actually uses, in place of the
+    /// one the run asked for.
+    pub fn record_profile(&mut self, service_id: &str, profile: &str) {
+        self.profiles.insert(service_id.to_string(), profile.to_string());
+    }
+}
+
+/// The answer one chain step produced, as stored with the run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepResult {
+    pub index: usize,
+    pub service_id: String,
+    pub label: String,
+    #[serde(default)]
+    pub model: Option<String>,
+    pub answer: String,
+    // Permalink of the thread the answer is in
+    #[serde(default)]
+    pub conversation_url: Option<String>,
+    // The step started in a new conversation
+    #[serde(default)]
+    pub fresh_context: bool,
+    // Code blocks of the answer, filled in when the step is recorded
+    #[serde(default)]
+    pub artifacts: Vec<CodeArtifact>,
+    // Linked sources, numbered like the answer's footnotes
+    #[serde(default)]
+    pub sources: Vec<Source>,
+    // Tables of the answer as headers and rows
+    #[serde(default)]
+    pub tables: Vec<Table>,
+    // Images and files of the answer saved in the run's artifact folder
+    #[serde(default)]
+    pub media: Vec<MediaItem>,
+    // How the answer was obtained; filled in when the step is recorded without one
+    #[serde(default)]
+    pub provenance: Option<Provenance>,
+    pub started_at: String,
+    pub finished_at: String,
+}
+
+#[derive(Default)]
+pub struct RunRegistry {
+    runs: Mutex<HashMap<String, RunMetadata>>,
+}
+
+impl RunRegistry {
+    pub fn get(&self, run_id: &str) -> Option<RunMetadata> {
+        self.runs.lock().unwrap().get(run_id).cloned()
+    }
+
+    /// Applies `f` to the run and persists the result.
+    pub fn update<F: FnOnce(&mut RunMetadata)>(&self, run_id: &str, f: F) -> Result<RunMetadata, String> {
+        let mut runs = self.runs.lock().unwrap();
+        let run = runs
+            .get_mut(run_id)
+            .ok_or_else(|| format!("Run '{}' not found", run_id))?;
+        f(run);
+        let snapshot = run.clone();
+        drop(runs);
+
+        save_run(&snapshot)?;
+        Ok(snapshot)
+    }
+
+    fn insert(&self, run: RunMetadata) {
+        self.runs.lock().unwrap().insert(run.run_id.clone(), run);
+    }
+}
+
+pub fn run_directory(run_id: &str) -> Result<PathBuf, String> {
+    get_app_data_directory()
+        .map(|dir| dir.join("runs").join(run_id))
+        .map_err(|e| format!("Failed to get app data directory: {}", e))
+}
+
+fn save_run(run: &RunMetadata) -> Result<(), String> {
+    let dir = run_directory(&run.run_id)?;
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create run directory: {}", e))?;
+
+    let content = serde_json::to_string_pretty(run).map_err(|e| e.to_string())?;
+    std::fs::write(dir.join("run.json"), content).map_err(|e| {
+        log_error("RUNS", &format!("Failed to save run {}: {}", run.run_id, e));
+        format!("Failed to save run: {}", e)
+    })
+}
+
+#[command]
+pub async fn start_run(
+    runs: tauri::State<'_, RunRegistry>,
+    chain: Vec<String>,
+    profiles: Option<HashMap<String, String>>,
+    window_mode: Option<WindowMode>,
+    service_window_modes: Option<HashMap<String, WindowMode>>,
+) -> Result<RunMetadata, String> {
+    // Steps are stored as `service` or `service:model`, without stray whitespace
+    let chain: Vec<String> = chain
+        .iter()
+        .map(|step| match parse_chain_step(step) {
+            ("", _) => Err(format!("Chain step '{}' names no service", step)),
+            (service, Some(model)) => Ok(format!("{}:{}", service, model)),
+            (service, None) => Ok(service.to_string()),
+        })
+        .collect::<Result<_, _>>()?;
+    let run_id = format!("run-{}", chrono::Utc::now().format("%Y%m%d-%H%M%S%.3f"));
+    log_with_context("RUNS", &format!("Starting {} for chain {:?}", run_id, chain));
+
+    let run = RunMetadata {
+        run_id,
+        chain,
+        status: "running".to_string(),
+        started_at: chrono::Utc::now().to_rfc3339(),
+        finished_at: None,
+        profiles: profiles.unwrap_or_default(),
+        window_mode: window_mode.unwrap_or_default(),
+        service_window_modes: service_window_modes.unwrap_or_default(),
+        warnings: Vec::new(),
+        incidents: Vec::new(),
+        attachments: HashMap::new(),
+        models: HashMap::new(),
+        steps: Vec::new(),
+    };
+    save_run(&run)?;
+    runs.insert(run.clone());
+    Ok(run)
+}
+
+#[command]
+pub async fn get_run(
+    runs: tauri::State<'_, RunRegistry>,
+    run_id: String,
+) -> Result<RunMetadata, String> {
+    runs.get(&run_id).ok_or_else(|| format!("Run '{}' not found", run_id))
+}
+
+#[command]
+pub async fn finish_run(
+    runs: tauri::State<'_, RunRegistry>,
+    run_id: String,
+    status: String,
+) -> Result<RunMetadata, String> {
+    log_with_context("RUNS", &format!("Finishing {} with status {}", run_id, status));
+    runs.update(&run_id, |run| {
+        run.status = status;
+        run.finished_at = Some(chrono::Utc::now().to_rfc3339());
+    })
+}
+
+/// Stores a finished step with the code artifacts and provenance of its
+/// answer; a step with the same index is replaced (retries).
+#[command]
+pub async fn record_step(
+    app: tauri::AppHandle,
+    runs: tauri::State<'_, RunRegistry>,
+    run_id: String,
+    mut step: StepResult,
+) -> Result<RunMetadata, String> {
+    step.artifacts = extract_artifacts(&step.answer, step.index);
+    let provenance = step.provenance.get_or_insert_with(|| Provenance {
+        model: step.model.clone(),
+        started_at: step.started_at.clone(),
+        finished_at: step.finished_at.clone(),
+        ..provenance::collect(&app, &step.label, &step.service_id, "unreported", None, &step.answer)
+    });
+    // The stored answer may be longer than the extracted one (continuations)
+    provenance.quality_score = provenance::quality_score(&step.answer);
+    log_with_context(
+        "RUNS",
+        &format!("{}: step {} ({}) finished, {} artifact(s)", run_id, step.index, step.service_id, step.artifacts.len()),
+    );
+    runs.update(&run_id, |run| {
+        run.steps.retain(|existing| existing.index != step.index);
+        run.steps.push(step);
+        run.steps.sort_by_key(|step| step.index);
+    })
+}
+
+/// Ties an already open (pooled) window to a run, so what happens in it is
+/// recorded there, and moves it into the window mode the run asks for.
+#[command]
+pub async fn link_webview_run(
+    app: tauri::AppHandle,
+    label: String,
+    run_id: String,
+    service_id: Option<String>,
+) -> Result<(), String> {
+    let session = app
+        .state::<WebviewSessions>()
+        .update(&label, |session| {
+            session.run_id = Some(run_id.clone());
+            if session.service_id.is_none() {
+                session.service_id = service_id;
+            }
+        })
+        .ok_or_else(|| format!("No session for window '{}'", label))?;
+    // A reused window keeps the profile it was opened with
+    let run = match session.service_id.as_deref() {
+        Some(service_id) => Some(
+            app.state::<RunRegistry>()
+                .update(&run_id, |run| run.record_profile(service_id, &session.profile))?,
+        ),
+        None => app.state::<RunRegistry>().get(&run_id),
+    };
+
+    let mode = background::window_mode_for(None, run.as_ref(), session.service_id.as_deref());
+    if mode != session.window_mode {
+        if let Some(window) = app.get_window(&label) {
+            background::apply_to_window(&window, mode);
+        }
+        app.state::<WebviewSessions>().update(&label, |session| session.window_mode = mode);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_linked_window_profile_replaces_the_requested_one() {
+        let mut run = RunMetadata {
+            run_id: "run-1".to_string(),
+            profiles: HashMap::from([("chatgpt".to_string(), "default".to_string())]),
+            ..RunMetadata::default()
+        };
+        run.record_profile("chatgpt", "work");
+        run.record_profile("claude", "default");
+        assert_eq!(run.profiles["chatgpt"], "work");
+        assert_eq!(run.profiles["claude"], "default");
+    }
+}
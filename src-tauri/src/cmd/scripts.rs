@@ -0,0 +1,154 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::time::Duration;
+use tauri::command;
+use crate::cmd::bridge::eval_with_result;
+
+// Every script the backend injects into pages, embedded from `scripts/`.
+// Each file is the body of an `async function (args)`; parameters reach it
+// only through the JSON `args` object. Bump `@version` when a script changes.
+const SCRIPTS: &[(&str, &str)] = &[
+    ("attach_files", include_str!("../../scripts/attach_files.js")),
+    ("collect_media", include_str!("../../scripts/collect_media.js")),
+    ("completion_probe", include_str!("../../scripts/completion_probe.js")),
+    ("console_bridge", include_str!("../../scripts/console_bridge.js")),
+    ("content_chunk", include_str!("../../scripts/content_chunk.js")),
+    ("content_metadata", include_str!("../../scripts/content_metadata.js")),
+    ("continuation_probe", include_str!("../../scripts/continuation_probe.js")),
+    ("conversation_turns", include_str!("../../scripts/conversation_turns.js")),
+    ("dom_snapshot", include_str!("../../scripts/dom_snapshot.js")),
+    ("error_probe", include_str!("../../scripts/error_probe.js")),
+    ("extract_content", include_str!("../../scripts/extract_content.js")),
+    ("extract_monitored", include_str!("../../scripts/extract_monitored.js")),
+    ("extract_response", include_str!("../../scripts/extract_response.js")),
+    ("force_navigation", include_str!("../../scripts/force_navigation.js")),
+    ("heartbeat", include_str!("../../scripts/heartbeat.js")),
+    ("inject_prompt", include_str!("../../scripts/inject_prompt.js")),
+    ("login_probe", include_str!("../../scripts/login_probe.js")),
+    ("model_picker", include_str!("../../scripts/model_picker.js")),
+    ("navigate", include_str!("../../scripts/navigate.js")),
+    ("new_chat", include_str!("../../scripts/new_chat.js")),
+    ("take_small_content", include_str!("../../scripts/take_small_content.js")),
+    ("text_content", include_str!("../../scripts/text_content.js")),
+    ("throttle_probe", include_str!("../../scripts/throttle_probe.js")),
+    ("title_completion_test", include_str!("../../scripts/title_completion_test.js")),
+    ("title_test", include_str!("../../scripts/title_test.js")),
+];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScriptInfo {
+    pub name: String,
+    pub version: String,
+    pub description: String,
+    pub params: Vec<String>,
+}
+
+fn source(name: &str) -> Result<&'static str, String> {
+    SCRIPTS
+        .iter()
+        .find(|(script, _)| *script == name)
+        .map(|(_, source)| *source)
+        .ok_or_else(|| format!("Unknown script '{}'", name))
+}
+
+// Values of `// @tag value` header lines
+fn header<'a>(source: &'a str, tag: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+    source
+        .lines()
+        .take_while(|line| line.starts_with("//"))
+        .filter_map(move |line| line.strip_prefix("// @")?.strip_prefix(tag)?.strip_prefix(' '))
+        .map(str::trim)
+}
+
+fn info(name: &str, source: &str) -> ScriptInfo {
+    ScriptInfo {
+        name: name.to_string(),
+        version: header(source, "version").next().unwrap_or("0").to_string(),
+        description: header(source, "description").next().unwrap_or_default().to_string(),
+        params: header(source, "param")
+            .map(|param| param.split(" - ").next().unwrap_or(param).to_string())
+            .collect(),
+    }
+}
+
+pub fn list() -> Vec<ScriptInfo> {
+    SCRIPTS.iter().map(|(name, source)| info(name, source)).collect()
+}
+
+/// The script as a self-contained expression that evaluates to a Promise.
+pub fn render(name: &str, args: &Value) -> Result<String, String> {
+    Ok(format!("(async function (args) {{\n{}\n}})({});", source(name)?, args))
+}
+
+/// Runs a script and waits for its return value through the eval bridge.
+pub async fn run_script(window: &tauri::Window, name: &str, args: &Value, timeout: Duration) -> Result<Value, String> {
+    let script = render(name, args)?;
+    eval_with_result(window, &format!("return {}", script), timeout).await
+}
+
+/// Runs a script without waiting for a result (title-signal scripts, pages without IPC).
+pub fn eval_script(window: &tauri::Window, name: &str, args: &Value) -> Result<(), String> {
+    window
+        .eval(&render(name, args)?)
+        .map_err(|e| format!("Script '{}' failed: {}", name, e))
+}
+
+#[command]
+pub async fn list_scripts() -> Result<Vec<ScriptInfo>, String> {
+    Ok(list())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::collections::BTreeSet;
+
+    // Identifiers used as `args.<name>` in the script body
+    fn used_args(source: &str) -> BTreeSet<String> {
+        source
+            .match_indices("args.")
+            .filter(|(i, _)| *i == 0 || !source[..*i].ends_with(|c: char| c.is_alphanumeric() || c == '_'))
+            .map(|(i, _)| {
+                source[i + 5..]
+                    .chars()
+                    .take_while(|c| c.is_alphanumeric() || *c == '_')
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_every_script_has_a_header() {
+        for script in list() {
+            assert_ne!(script.version, "0", "{} has no @version", script.name);
+            assert!(!script.description.is_empty(), "{} has no @description", script.name);
+        }
+    }
+
+    #[test]
+    fn test_registry_is_sorted() {
+        let names: Vec<&str> = SCRIPTS.iter().map(|(name, _)| *name).collect();
+        let mut sorted = names.clone();
+        sorted.sort_unstable();
+        assert_eq!(names, sorted);
+    }
+
+    #[test]
+    fn test_declared_params_match_usage() {
+        for (name, source) in SCRIPTS {
+            let declared: BTreeSet<String> = info(name, source).params.into_iter().collect();
+            assert_eq!(declared, used_args(source), "@param lines of {} do not match args usage", name);
+        }
+    }
+
+    #[test]
+    fn test_render_passes_args_as_json() {
+        let args = json!({ "selector": "a[title='x'] \"quoted\" </script>" });
+        let script = render("text_content", &args).unwrap();
+        assert!(script.starts_with("(async function (args) {"));
+        let passed = script.rsplit(")(").next().unwrap().trim_end_matches(");");
+        assert_eq!(serde_json::from_str::<Value>(passed).unwrap(), args);
+        assert!(render("no_such_script", &args).is_err());
+    }
+}
@@ -0,0 +1,294 @@
+use chrono::{NaiveTime, Timelike};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::fmt;
+use std::time::Duration;
+use tauri::{command, Manager};
+use crate::cmd::config::service_selectors;
+use crate::cmd::guard;
+use crate::cmd::runs::RunRegistry;
+use crate::cmd::scripts::run_script;
+use crate::cmd::sessions::WebviewSessions;
+use crate::utils::log_error;
+
+pub const SERVICE_ERROR_EVENT: &str = "service://error";
+
+const PROBE_TIMEOUT_MS: u64 = 3000;
+const MESSAGE_EXCERPT: usize = 300;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ServiceErrorKind {
+    RateLimited,
+    Capacity,
+    NetworkError,
+    ContentPolicyRefusal,
+}
+
+/// One kind of error page: any visible selector match, or a pattern in a
+/// short response, means the service answered with this error.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ErrorSignature {
+    pub kind: ServiceErrorKind,
+    // Case-insensitive substrings
+    #[serde(default)]
+    pub patterns: Vec<String>,
+    #[serde(default)]
+    pub selectors: Vec<String>,
+    // Longer responses are real answers that merely mention the pattern
+    #[serde(default = "default_max_response_length")]
+    pub max_response_length: usize,
+}
+
+fn default_max_response_length() -> usize {
+    600
+}
+
+/// Why a step produced no usable answer; callers retry or reroute on this.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ServiceFailure {
+    // Seconds until the service accepts messages again, when it said so
+    RateLimited { retry_after: Option<u64> },
+    Capacity,
+    NetworkError,
+    ContentPolicyRefusal,
+}
+
+impl fmt::Display for ServiceFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ServiceFailure::RateLimited { retry_after: Some(secs) } => write!(f, "rate limited, retry after {} s", secs),
+            ServiceFailure::RateLimited { retry_after: None } => write!(f, "rate limited"),
+            ServiceFailure::Capacity => write!(f, "service at capacity"),
+            ServiceFailure::NetworkError => write!(f, "network error"),
+            ServiceFailure::ContentPolicyRefusal => write!(f, "content policy refusal"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ServiceErrorReport {
+    pub label: String,
+    pub service_id: String,
+    pub failure: ServiceFailure,
+    // Selector or pattern that matched
+    pub matched: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SelectorHit {
+    // Index of the signature in the service config
+    pub signature: usize,
+    pub selector: String,
+    pub text: String,
+}
+
+fn normalize(text: &str) -> String {
+    text.replace(['\u{2019}', '\u{2018}'], "'").to_lowercase()
+}
+
+fn unit_seconds(word: &str) -> Option<u64> {
+    if word.starts_with("sec") {
+        Some(1)
+    } else if word.starts_with("min") {
+        Some(60)
+    } else if word.starts_with("hour") || word.starts_with("hr") {
+        Some(3600)
+    } else if word.starts_with("day") {
+        Some(86400)
+    } else {
+        None
+    }
+}
+
+// "5:42 pm", "5:42pm" or "17:00"
+fn clock_time(token: &str, next: Option<&str>) -> Option<NaiveTime> {
+    let (clock, suffix) = match token.find(['a', 'p']) {
+        Some(i) => (&token[..i], &token[i..]),
+        None => (token, next.unwrap_or("")),
+    };
+    let (hours, minutes) = clock.split_once(':')?;
+    let mut hours: u32 = hours.parse().ok()?;
+    let minutes: u32 = minutes.parse().ok()?;
+    match suffix.trim_matches('.') {
+        "pm" | "p.m" if hours < 12 => hours += 12,
+        "am" | "a.m" if hours == 12 => hours = 0,
+        _ => {}
+    }
+    NaiveTime::from_hms_opt(hours, minutes, 0)
+}
+
+/// Seconds until the service can be used again, from text such as
+/// "try again in 1 hour and 30 minutes" or "resets at 5:42 PM".
+pub fn parse_retry_after(text: &str, now: NaiveTime) -> Option<u64> {
+    let text = normalize(text);
+    let tokens: Vec<&str> = text
+        .split(|c: char| c.is_whitespace() || matches!(c, ',' | '(' | ')' | '!' | '?'))
+        .filter(|token| !token.is_empty())
+        .collect();
+
+    let mut total = 0;
+    for (i, token) in tokens.iter().enumerate() {
+        let next = tokens.get(i + 1).copied();
+        if token.contains(':') {
+            if let Some(at) = clock_time(token.trim_end_matches('.'), next) {
+                let now = now.num_seconds_from_midnight() as i64;
+                let wait = (at.num_seconds_from_midnight() as i64 - now).rem_euclid(86400);
+                return Some(wait as u64);
+            }
+        }
+        if let (Ok(amount), Some(unit)) = (token.parse::<u64>(), next.and_then(unit_seconds)) {
+            total += amount * unit;
+        }
+    }
+    (total > 0).then_some(total)
+}
+
+fn failure_for(kind: ServiceErrorKind, text: &str, now: NaiveTime) -> ServiceFailure {
+    match kind {
+        ServiceErrorKind::RateLimited => ServiceFailure::RateLimited { retry_after: parse_retry_after(text, now) },
+        ServiceErrorKind::Capacity => ServiceFailure::Capacity,
+        ServiceErrorKind::NetworkError => ServiceFailure::NetworkError,
+        ServiceErrorKind::ContentPolicyRefusal => ServiceFailure::ContentPolicyRefusal,
+    }
+}
+
+/// First signature, in config order, matched by a visible element or the response text.
+pub fn classify(
+    signatures: &[ErrorSignature],
+    response: &str,
+    hits: &[SelectorHit],
+    now: NaiveTime,
+) -> Option<(ServiceFailure, String, String)> {
+    let response_lower = normalize(response);
+    signatures.iter().enumerate().find_map(|(index, signature)| {
+        if let Some(hit) = hits.iter().find(|hit| hit.signature == index) {
+            return Some((failure_for(signature.kind, &hit.text, now), hit.selector.clone(), hit.text.clone()));
+        }
+        if response.trim().is_empty() || response.chars().count() > signature.max_response_length {
+            return None;
+        }
+        signature
+            .patterns
+            .iter()
+            .find(|pattern| !pattern.is_empty() && response_lower.contains(&normalize(pattern)))
+            .map(|pattern| (failure_for(signature.kind, response, now), pattern.clone(), response.trim().to_string()))
+    })
+}
+
+async fn selector_hits(window: &tauri::Window, signatures: &[ErrorSignature]) -> Result<Vec<SelectorHit>, String> {
+    let groups: Vec<&Vec<String>> = signatures.iter().map(|signature| &signature.selectors).collect();
+    if groups.iter().all(|selectors| selectors.is_empty()) {
+        return Ok(Vec::new());
+    }
+    let value = run_script(window, "error_probe", &json!({ "groups": groups }), Duration::from_millis(PROBE_TIMEOUT_MS)).await?;
+    serde_json::from_value(value).map_err(|e| format!("Invalid error probe: {}", e))
+}
+
+/// Checks the service window (and the response taken from it) against the
+/// service's error signatures. A match is logged, noted on the run and emitted
+/// as `service://error`.
+pub async fn detect_service_error(
+    app: &tauri::AppHandle,
+    label: &str,
+    service_id: Option<String>,
+    response: &str,
+) -> Result<Option<ServiceErrorReport>, String> {
+    let window = app
+        .get_window(label)
+        .ok_or_else(|| "Window not found".to_string())?;
+    let session = app.state::<WebviewSessions>().get(label);
+    let service_id = service_id
+        .or_else(|| session.as_ref().and_then(|s| s.service_id.clone()))
+        .ok_or_else(|| format!("No service known for window '{}'", label))?;
+    let signatures = service_selectors(&service_id)?.error_signatures;
+
+    let hits = if guard::has_ipc_access(app, &window.url()) {
+        selector_hits(&window, &signatures).await?
+    } else {
+        Vec::new()
+    };
+    let now = chrono::Local::now().time();
+    let Some((failure, matched, message)) = classify(&signatures, response, &hits, now) else {
+        return Ok(None);
+    };
+
+    let report = ServiceErrorReport {
+        label: label.to_string(),
+        service_id: service_id.clone(),
+        failure,
+        matched,
+        message: message.chars().take(MESSAGE_EXCERPT).collect(),
+    };
+    let note = format!("{} ({}): {} - matched '{}'", label, service_id, report.failure, report.matched);
+    log_error("SERVICE", &note);
+    if let Some(run_id) = session.and_then(|s| s.run_id) {
+        let _ = app.state::<RunRegistry>().update(&run_id, |run| run.warnings.push(note));
+    }
+    let _ = app.emit_all(SERVICE_ERROR_EVENT, report.clone());
+    Ok(Some(report))
+}
+
+#[command]
+pub async fn check_service_error(
+    app: tauri::AppHandle,
+    label: String,
+    service_id: Option<String>,
+    response: Option<String>,
+) -> Result<Option<ServiceErrorReport>, String> {
+    detect_service_error(&app, &label, service_id, &response.unwrap_or_default()).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn noon() -> NaiveTime {
+        NaiveTime::from_hms_opt(12, 0, 0).unwrap()
+    }
+
+    fn signature(kind: ServiceErrorKind, pattern: &str) -> ErrorSignature {
+        ErrorSignature {
+            kind,
+            patterns: vec![pattern.to_string()],
+            selectors: Vec::new(),
+            max_response_length: default_max_response_length(),
+        }
+    }
+
+    #[test]
+    fn test_retry_after_from_durations_and_clock_times() {
+        assert_eq!(parse_retry_after("Try again in 1 hour and 30 minutes.", noon()), Some(5400));
+        assert_eq!(parse_retry_after("Your limit resets at 5:42 PM", noon()), Some(5 * 3600 + 42 * 60));
+        // A time earlier than now is tomorrow
+        assert_eq!(parse_retry_after("available again after 11:00", noon()), Some(23 * 3600));
+        assert_eq!(parse_retry_after("You've reached your limit", noon()), None);
+    }
+
+    #[test]
+    fn test_short_error_text_is_classified() {
+        let signatures = vec![
+            signature(ServiceErrorKind::RateLimited, "You've reached your limit"),
+            signature(ServiceErrorKind::Capacity, "is at capacity"),
+        ];
+        let (failure, matched, _) =
+            classify(&signatures, "You\u{2019}ve reached your limit. Try again in 2 hours.", &[], noon()).unwrap();
+        assert_eq!(failure, ServiceFailure::RateLimited { retry_after: Some(7200) });
+        assert_eq!(matched, "You've reached your limit");
+        let (failure, _, _) = classify(&signatures, "Claude is at capacity right now", &[], noon()).unwrap();
+        assert_eq!(failure, ServiceFailure::Capacity);
+    }
+
+    #[test]
+    fn test_long_answers_and_selector_hits() {
+        let signatures = vec![signature(ServiceErrorKind::Capacity, "at capacity")];
+        let answer = format!("When a server is at capacity, {}", "it queues requests. ".repeat(50));
+        assert!(classify(&signatures, &answer, &[], noon()).is_none());
+        let hits = vec![SelectorHit { signature: 0, selector: ".error".to_string(), text: "Busy".to_string() }];
+        let (failure, matched, message) = classify(&signatures, &answer, &hits, noon()).unwrap();
+        assert_eq!((failure, matched.as_str(), message.as_str()), (ServiceFailure::Capacity, ".error", "Busy"));
+    }
+}
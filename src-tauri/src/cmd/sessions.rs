@@ -0,0 +1,65 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use crate::cmd::background::WindowMode;
+
+// What the backend knows about each service window it created
+#[derive(Debug, Clone, Serialize)]
+pub struct WebviewSession {
+    pub label: String,
+    pub url: String,
+    pub service_id: Option<String>,
+    pub profile: String,
+    pub run_id: Option<String>,
+    pub window_mode: WindowMode,
+    pub created_at: String,
+    // When the latest wait for an answer began, and why it ended
+    pub answer_started_at: Option<String>,
+    pub completion_reason: Option<String>,
+    // Response text on the page right before the latest prompt was sent
+    #[serde(skip)]
+    pub response_before_send: Option<String>,
+    // Model shown as active when the latest prompt was sent
+    pub active_model: Option<String>,
+}
+
+#[derive(Default)]
+pub struct WebviewSessions {
+    sessions: Mutex<HashMap<String, WebviewSession>>,
+}
+
+impl WebviewSessions {
+    pub fn insert(&self, session: WebviewSession) {
+        self.sessions.lock().unwrap().insert(session.label.clone(), session);
+    }
+
+    pub fn remove(&self, label: &str) -> Option<WebviewSession> {
+        self.sessions.lock().unwrap().remove(label)
+    }
+
+    pub fn get(&self, label: &str) -> Option<WebviewSession> {
+        self.sessions.lock().unwrap().get(label).cloned()
+    }
+
+    /// Applies `f` to the session of `label`, if there is one.
+    pub fn update<F: FnOnce(&mut WebviewSession)>(&self, label: &str, f: F) -> Option<WebviewSession> {
+        let mut sessions = self.sessions.lock().unwrap();
+        let session = sessions.get_mut(label)?;
+        f(session);
+        Some(session.clone())
+    }
+
+    pub fn all(&self) -> Vec<WebviewSession> {
+        self.sessions.lock().unwrap().values().cloned().collect()
+    }
+
+    pub fn labels_using_profile(&self, profile: &str) -> Vec<String> {
+        self.sessions
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|s| s.profile == profile)
+            .map(|s| s.label.clone())
+            .collect()
+    }
+}
@@ -0,0 +1,254 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::{command, Manager};
+use crate::cmd::config::service_selectors;
+use crate::cmd::console::{ConsoleEntry, ConsoleLevel, WebviewConsole};
+use crate::cmd::guard;
+use crate::cmd::runs::RunRegistry;
+use crate::cmd::scripts::run_script;
+use crate::cmd::sessions::WebviewSessions;
+use crate::utils::{get_app_data_directory, log_error, log_warning};
+
+// Pages like ChatGPT can serialize to tens of megabytes with long conversations
+const MAX_HTML_LENGTH: usize = 8 * 1024 * 1024;
+const DOM_CAPTURE_TIMEOUT_MS: u64 = 10000;
+const CONSOLE_TAIL: usize = 50;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SelectorMatch {
+    pub group: String,
+    pub selector: String,
+    pub count: u32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct DomCapture {
+    url: String,
+    title: String,
+    html: String,
+    truncated: bool,
+    selectors: Vec<SelectorMatch>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IncidentReport {
+    pub incident_id: String,
+    pub directory: String,
+    pub label: String,
+    pub service_id: Option<String>,
+    pub run_id: Option<String>,
+    pub reason: String,
+    pub captured_at: String,
+    pub url: String,
+    pub title: String,
+    pub selectors: Vec<SelectorMatch>,
+    pub html_file: Option<String>,
+    pub html_truncated: bool,
+    pub dom_error: Option<String>,
+    pub screenshot_file: Option<String>,
+    pub screenshot_error: Option<String>,
+    pub console: Vec<ConsoleEntry>,
+}
+
+fn incident_directory(incident_id: &str) -> Result<PathBuf, String> {
+    get_app_data_directory()
+        .map(|dir| dir.join("incidents").join(incident_id))
+        .map_err(|e| format!("Failed to get app data directory: {}", e))
+}
+
+// Every configured selector for the service, grouped by what it is for
+fn selector_groups(service_id: Option<&str>) -> Vec<(String, Vec<String>)> {
+    let service = match service_id.map(service_selectors) {
+        Some(Ok(service)) => service,
+        _ => return Vec::new(),
+    };
+    vec![
+        ("input".to_string(), service.input_selectors),
+        ("send".to_string(), service.send_selectors),
+        ("response".to_string(), service.response_selectors),
+        ("logged_in".to_string(), service.login.logged_in_selectors),
+        ("logged_out".to_string(), service.login.logged_out_selectors),
+        ("challenge".to_string(), service.login.challenge_selectors),
+    ]
+}
+
+async fn capture_dom(window: &tauri::Window, groups: &[(String, Vec<String>)]) -> Result<DomCapture, String> {
+    let args = json!({ "groups": groups, "maxHtmlLength": MAX_HTML_LENGTH });
+    let value = run_script(window, "dom_snapshot", &args, Duration::from_millis(DOM_CAPTURE_TIMEOUT_MS)).await?;
+    serde_json::from_value(value).map_err(|e| format!("Invalid DOM capture: {}", e))
+}
+
+#[cfg(windows)]
+const SCREENSHOT_TIMEOUT_MS: u64 = 10000;
+
+/// PNG of the visible page through WebView2 `CapturePreview`.
+#[cfg(windows)]
+async fn capture_screenshot(window: &tauri::Window) -> Result<Vec<u8>, String> {
+    use webview2_com::CapturePreviewCompletedHandler;
+    use webview2_com::Microsoft::Web::WebView2::Win32::COREWEBVIEW2_CAPTURE_PREVIEW_IMAGE_FORMAT_PNG;
+    use windows::Win32::System::Com::{IStream, STREAM_SEEK_SET};
+    use windows::Win32::UI::Shell::SHCreateMemStream;
+
+    fn read_stream(stream: &IStream) -> Result<Vec<u8>, String> {
+        let mut bytes = Vec::new();
+        let mut chunk = [0u8; 64 * 1024];
+        unsafe {
+            stream.Seek(0, STREAM_SEEK_SET).map_err(|e| e.to_string())?;
+            loop {
+                let mut read = 0u32;
+                stream
+                    .Read(chunk.as_mut_ptr() as *mut _, chunk.len() as u32, &mut read)
+                    .ok()
+                    .map_err(|e| e.to_string())?;
+                if read == 0 {
+                    break;
+                }
+                bytes.extend_from_slice(&chunk[..read as usize]);
+            }
+        }
+        Ok(bytes)
+    }
+
+    let (tx, rx) = tokio::sync::oneshot::channel::<Result<Vec<u8>, String>>();
+    window
+        .with_webview(move |webview| unsafe {
+            let stream = match SHCreateMemStream(std::ptr::null(), 0) {
+                Some(stream) => stream,
+                None => {
+                    let _ = tx.send(Err("Failed to create image stream".to_string()));
+                    return;
+                }
+            };
+            let core = match webview.controller().CoreWebView2() {
+                Ok(core) => core,
+                Err(e) => {
+                    let _ = tx.send(Err(e.to_string()));
+                    return;
+                }
+            };
+
+            let target = stream.clone();
+            let handler = CapturePreviewCompletedHandler::create(Box::new(move |result| {
+                let _ = tx.send(result.map_err(|e| e.to_string()).and_then(|_| read_stream(&target)));
+                Ok(())
+            }));
+            // On failure the handler (and the sender) is dropped, which ends the wait below
+            if let Err(e) = core.CapturePreview(COREWEBVIEW2_CAPTURE_PREVIEW_IMAGE_FORMAT_PNG, &stream, &handler) {
+                log_warning("SNAPSHOT", &format!("CapturePreview failed: {}", e));
+            }
+        })
+        .map_err(|e| format!("Failed to access webview: {}", e))?;
+
+    match tokio::time::timeout(Duration::from_millis(SCREENSHOT_TIMEOUT_MS), rx).await {
+        Ok(Ok(result)) => result,
+        Ok(Err(_)) => Err("Screenshot capture was cancelled".to_string()),
+        Err(_) => Err("Screenshot capture timed out".to_string()),
+    }
+}
+
+#[cfg(not(windows))]
+async fn capture_screenshot(_window: &tauri::Window) -> Result<Vec<u8>, String> {
+    Err("Screenshots are only supported with WebView2 (Windows)".to_string())
+}
+
+/// Saves everything needed to debug a failed step into
+/// `<app data>/incidents/<id>/`: the page HTML, a screenshot where the
+/// platform allows it, and `incident.json` with URL, title, selector matches
+/// and the tail of the page console. The incident is linked from the run.
+pub async fn save_failure_snapshot(
+    app: &tauri::AppHandle,
+    label: &str,
+    reason: &str,
+    service_id: Option<String>,
+    run_id: Option<String>,
+) -> Result<IncidentReport, String> {
+    let window = app
+        .get_window(label)
+        .ok_or_else(|| "Window not found".to_string())?;
+    let session = app.state::<WebviewSessions>().get(label);
+    let service_id = service_id.or_else(|| session.as_ref().and_then(|s| s.service_id.clone()));
+    let run_id = run_id.or_else(|| session.as_ref().and_then(|s| s.run_id.clone()));
+
+    let now = chrono::Utc::now();
+    let incident_id = format!("{}-{}", now.format("%Y%m%d-%H%M%S%.3f"), label);
+    let dir = incident_directory(&incident_id)?;
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create incident directory: {}", e))?;
+
+    let groups = selector_groups(service_id.as_deref());
+    let page_url = window.url();
+    let dom = if guard::has_ipc_access(app, &page_url) {
+        capture_dom(&window, &groups).await
+    } else {
+        Err("Page has no IPC access; DOM not captured".to_string())
+    };
+
+    let mut report = IncidentReport {
+        incident_id: incident_id.clone(),
+        directory: dir.to_string_lossy().to_string(),
+        label: label.to_string(),
+        service_id,
+        run_id: run_id.clone(),
+        reason: reason.to_string(),
+        captured_at: now.to_rfc3339(),
+        url: page_url.to_string(),
+        title: window.title().unwrap_or_default(),
+        selectors: Vec::new(),
+        html_file: None,
+        html_truncated: false,
+        dom_error: None,
+        screenshot_file: None,
+        screenshot_error: None,
+        console: app.state::<WebviewConsole>().recent(label, ConsoleLevel::Debug, CONSOLE_TAIL),
+    };
+
+    match dom {
+        Ok(dom) => {
+            report.url = dom.url;
+            report.title = dom.title;
+            report.selectors = dom.selectors;
+            report.html_truncated = dom.truncated;
+            match std::fs::write(dir.join("page.html"), dom.html) {
+                Ok(_) => report.html_file = Some("page.html".to_string()),
+                Err(e) => report.dom_error = Some(format!("Failed to write page.html: {}", e)),
+            }
+        }
+        Err(e) => report.dom_error = Some(e),
+    }
+
+    match capture_screenshot(&window).await {
+        Ok(png) => match std::fs::write(dir.join("screenshot.png"), png) {
+            Ok(_) => report.screenshot_file = Some("screenshot.png".to_string()),
+            Err(e) => report.screenshot_error = Some(format!("Failed to write screenshot.png: {}", e)),
+        },
+        Err(e) => report.screenshot_error = Some(e),
+    }
+
+    let content = serde_json::to_string_pretty(&report).map_err(|e| e.to_string())?;
+    std::fs::write(dir.join("incident.json"), content)
+        .map_err(|e| format!("Failed to save incident: {}", e))?;
+
+    log_error(
+        "SNAPSHOT",
+        &format!("{} failed ({}); incident saved to {}", label, reason, report.directory),
+    );
+    if let Some(run_id) = run_id.as_deref() {
+        let directory = report.directory.clone();
+        if let Err(e) = app.state::<RunRegistry>().update(run_id, |run| run.incidents.push(directory)) {
+            log_warning("SNAPSHOT", &format!("Could not link incident to {}: {}", run_id, e));
+        }
+    }
+    Ok(report)
+}
+
+#[command]
+pub async fn capture_failure_snapshot(
+    app: tauri::AppHandle,
+    label: String,
+    reason: String,
+    service_id: Option<String>,
+    run_id: Option<String>,
+) -> Result<IncidentReport, String> {
+    save_failure_snapshot(&app, &label, &reason, service_id, run_id).await
+}
@@ -0,0 +1,252 @@
+use kuchikiki::NodeRef;
+use serde::{Deserialize, Serialize};
+use tauri::Url;
+use crate::cmd::markdown::{attr, document_to_markdown, tag};
+
+// Elements whose text is "the sentence" around a link
+const SENTENCE_BLOCKS: &[&str] = &[
+    "p", "li", "td", "th", "blockquote", "dd", "figcaption", "h1", "h2", "h3", "h4", "h5", "h6", "div",
+];
+
+/// A linked source of an answer. Numbers follow the order of first
+/// reference, whatever numbering the service used.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Source {
+    pub number: usize,
+    pub url: String,
+    pub title: String,
+    // Sentence of the first reference
+    pub sentence: String,
+}
+
+/// Drops fragments, tracking parameters and redirect wrappers so the same
+/// page cited twice is one source.
+pub fn normalize_url(href: &str) -> Option<String> {
+    let mut url = Url::parse(href.trim()).ok()?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return None;
+    }
+    if url.host_str().is_some_and(|host| host.ends_with("google.com")) && url.path() == "/url" {
+        let target = url.query_pairs().find(|(key, _)| key == "q" || key == "url").map(|(_, value)| value.to_string());
+        if let Some(target) = target {
+            return normalize_url(&target);
+        }
+    }
+    url.set_fragment(None);
+    let kept: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(key, _)| !key.starts_with("utm_") && key != "ref" && key != "fbclid" && key != "gclid")
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+    if kept.is_empty() {
+        url.set_query(None);
+    } else {
+        url.query_pairs_mut().clear().extend_pairs(kept);
+    }
+    let mut normalized = url.to_string();
+    if url.path() != "/" && url.query().is_none() && normalized.ends_with('/') {
+        normalized.pop();
+    }
+    Some(normalized)
+}
+
+// "[2]", "(3)", "⁴", a citation/footnote class or an anchor inside <sup>. A
+// bare number is ordinary link text (a year, a version) unless marked up so.
+fn is_citation_marker(anchor: &NodeRef) -> bool {
+    let text = anchor.text_contents();
+    let text = text.trim();
+    let bracketed = [('[', ']'), ('(', ')')].iter().any(|(open, close)| {
+        text.strip_prefix(*open)
+            .and_then(|inner| inner.strip_suffix(*close))
+            .is_some_and(|inner| !inner.is_empty() && inner.len() <= 4 && inner.chars().all(|c| c.is_ascii_digit()))
+    });
+    let superscript = !text.is_empty() && text.chars().count() <= 4 && text.chars().all(|c| "⁰¹²³⁴⁵⁶⁷⁸⁹".contains(c));
+    let in_sup = anchor.ancestors().take(2).any(|node| tag(&node).as_deref() == Some("sup"));
+    let classed = attr(anchor, "class").is_some_and(|class| class.contains("citation") || class.contains("footnote"));
+    bracketed || superscript || in_sup || classed
+}
+
+// Positions just after a sentence end
+fn sentence_starts(text: &str) -> Vec<usize> {
+    let mut starts = vec![0];
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let next_is_space = chars.peek().is_some_and(|(_, next)| next.is_whitespace());
+        if c == '\n' || (matches!(c, '.' | '!' | '?') && next_is_space) {
+            starts.push(i + c.len_utf8());
+        }
+    }
+    starts
+}
+
+fn sentence_at(text: &str, offset: usize, citation: bool) -> String {
+    // A citation follows the sentence it supports
+    let head = text[..offset].trim_end();
+    let start = sentence_starts(text)
+        .into_iter()
+        .rfind(|start| if citation { *start < head.len() } else { *start <= offset })
+        .unwrap_or(0);
+    let end = if citation {
+        head.len()
+    } else {
+        sentence_starts(&text[offset..])
+            .get(1)
+            .map(|end| offset + end)
+            .unwrap_or(text.len())
+    };
+    text[start..end.max(start)].split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Text of the anchor's sentence block with citation markers left out, and
+/// where the anchor starts in it.
+fn anchor_context(anchor: &NodeRef) -> (String, usize) {
+    let block = anchor
+        .ancestors()
+        .find(|node| tag(node).is_some_and(|name| SENTENCE_BLOCKS.contains(&name.as_str())))
+        .unwrap_or_else(|| anchor.clone());
+    let mut text = String::new();
+    let mut offset = None;
+    for node in block.inclusive_descendants() {
+        if node == *anchor {
+            offset = Some(text.len());
+        }
+        let Some(content) = node.as_text() else { continue };
+        let in_marker = node
+            .ancestors()
+            .take_while(|ancestor| *ancestor != block)
+            .any(|ancestor| tag(&ancestor).as_deref() == Some("a") && is_citation_marker(&ancestor));
+        if !in_marker {
+            text.push_str(&content.borrow());
+        }
+    }
+    let offset = offset.unwrap_or(text.len());
+    (text, offset)
+}
+
+fn title_of(anchor: &NodeRef, citation: bool, url: &str) -> String {
+    let text = anchor.text_contents().split_whitespace().collect::<Vec<_>>().join(" ");
+    attr(anchor, "title")
+        .or_else(|| attr(anchor, "aria-label"))
+        .filter(|title| !title.trim().is_empty())
+        .or_else(|| (!citation && !text.is_empty()).then_some(text))
+        .or_else(|| Url::parse(url).ok().and_then(|url| url.host_str().map(str::to_string)))
+        .unwrap_or_else(|| url.to_string())
+}
+
+/// Collects the linked sources of a response document, deduplicated by URL,
+/// and replaces citation markers with `[^n]` footnote references in the
+/// renumbered order.
+pub fn collect_sources(document: &NodeRef) -> Vec<Source> {
+    let anchors: Vec<NodeRef> = match document.select("a[href]") {
+        Ok(anchors) => anchors.map(|anchor| anchor.as_node().clone()).collect(),
+        Err(_) => return Vec::new(),
+    };
+    // Sentences are read before any marker is replaced
+    let references: Vec<(NodeRef, String, bool, String)> = anchors
+        .into_iter()
+        .filter_map(|anchor| {
+            let url = normalize_url(&attr(&anchor, "href")?)?;
+            let citation = is_citation_marker(&anchor);
+            let (text, offset) = anchor_context(&anchor);
+            let sentence = sentence_at(&text, offset, citation);
+            Some((anchor, url, citation, sentence))
+        })
+        .collect();
+
+    let mut sources: Vec<Source> = Vec::new();
+    for (anchor, url, citation, sentence) in references {
+        let number = match sources.iter().find(|source| source.url == url) {
+            Some(source) => source.number,
+            None => {
+                sources.push(Source {
+                    number: sources.len() + 1,
+                    title: title_of(&anchor, citation, &url),
+                    url,
+                    sentence,
+                });
+                sources.len()
+            }
+        };
+        if citation {
+            anchor.insert_before(NodeRef::new_text(format!("[^{}]", number)));
+            anchor.detach();
+        }
+    }
+    sources
+}
+
+/// Footnote definitions for the sources, appended to the Markdown answer.
+pub fn footnotes(sources: &[Source]) -> String {
+    sources
+        .iter()
+        .map(|source| format!("[^{}]: [{}]({})", source.number, source.title.replace(['[', ']'], ""), source.url))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Markdown of the response with renumbered footnotes, and its sources.
+pub fn document_markdown_with_sources(document: &NodeRef) -> (String, Vec<Source>) {
+    let sources = collect_sources(document);
+    let markdown = document_to_markdown(document);
+    if sources.is_empty() {
+        return (markdown, sources);
+    }
+    (format!("{}\n\n{}", markdown, footnotes(&sources)), sources)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kuchikiki::traits::TendrilSink;
+
+    fn markdown_with_sources(html: &str) -> (String, Vec<Source>) {
+        document_markdown_with_sources(&kuchikiki::parse_html().one(html))
+    }
+
+    #[test]
+    fn test_urls_are_normalized() {
+        assert_eq!(
+            normalize_url("https://Example.com/page/?utm_source=chatgpt.com#section").as_deref(),
+            Some("https://example.com/page")
+        );
+        assert_eq!(
+            normalize_url("https://www.google.com/url?q=https://example.com/a%3Fx%3D1&sa=D").as_deref(),
+            Some("https://example.com/a?x=1")
+        );
+        assert_eq!(normalize_url("javascript:void(0)"), None);
+    }
+
+    #[test]
+    fn test_citations_are_deduplicated_and_renumbered() {
+        let html = r#"<p>Paris is the capital of France.<sup><a href="https://a.example/paris?utm_source=x" title="Paris - Wiki">4</a></sup>
+            It has 2.1 million people.<a class="citation" href="https://b.example/stats">[7]</a></p>
+            <p>See <a href="https://a.example/paris">the article</a> again.</p>"#;
+        let (markdown, sources) = markdown_with_sources(html);
+        assert_eq!(sources.len(), 2);
+        assert_eq!(sources[0].number, 1);
+        assert_eq!(sources[0].title, "Paris - Wiki");
+        assert_eq!(sources[0].sentence, "Paris is the capital of France.");
+        assert_eq!(sources[1].title, "b.example");
+        assert_eq!(sources[1].sentence, "It has 2.1 million people.");
+        assert!(markdown.starts_with("Paris is the capital of France.[^1] It has 2.1 million people.[^2]"));
+        assert!(markdown.contains("See [the article](https://a.example/paris) again."));
+        assert!(markdown.ends_with("[^1]: [Paris - Wiki](https://a.example/paris)\n[^2]: [b.example](https://b.example/stats)"));
+    }
+
+    #[test]
+    fn test_inline_links_keep_their_sentence() {
+        let html = r#"<ul><li>First point. Read <a href="https://docs.example/guide">the guide</a> for details. Last.</li></ul>"#;
+        let (_, sources) = markdown_with_sources(html);
+        assert_eq!(sources[0].title, "the guide");
+        assert_eq!(sources[0].sentence, "Read the guide for details.");
+    }
+
+    #[test]
+    fn test_bare_numbers_are_not_citations() {
+        let html = r#"<p>Released in <a href="https://news.example/2023">2023</a>, it shipped <a href="https://b.example/x">(2)</a> updates.</p>"#;
+        let (markdown, sources) = markdown_with_sources(html);
+        assert!(markdown.starts_with("Released in [2023](https://news.example/2023), it shipped"));
+        assert_eq!(sources[0].title, "2023");
+        assert_eq!(sources[1].title, "b.example");
+    }
+}
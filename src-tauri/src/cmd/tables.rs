@@ -0,0 +1,281 @@
+use kuchikiki::NodeRef;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tauri::command;
+use crate::cmd::markdown::{attr, tag};
+use crate::cmd::runs::RunRegistry;
+use crate::utils::log_with_context;
+
+// Spans beyond this are treated as typos rather than layout
+const MAX_SPAN: usize = 100;
+
+/// A table of an answer as plain cell text. Merged cells repeat their value
+/// in every row and column they cover, so each row has one value per header.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Table {
+    // Position among the answer's tables, from 0
+    pub index: usize,
+    #[serde(default)]
+    pub caption: Option<String>,
+    pub headers: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TableFormat {
+    Csv,
+    Json,
+}
+
+// Elements whose content does not run on from the text before them
+const BREAKS: &[&str] = &["br", "p", "div", "li", "tr", "td", "th", "h1", "h2", "h3", "h4", "h5", "h6"];
+
+fn text_of(node: &NodeRef) -> String {
+    let mut text = String::new();
+    for descendant in node.descendants() {
+        if let Some(content) = descendant.as_text() {
+            text.push_str(&content.borrow());
+        } else if tag(&descendant).is_some_and(|name| BREAKS.contains(&name.as_str())) {
+            text.push(' ');
+        }
+    }
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn span(cell: &NodeRef, name: &str) -> usize {
+    attr(cell, name)
+        .and_then(|value| value.trim().parse::<usize>().ok())
+        .unwrap_or(1)
+        .clamp(1, MAX_SPAN)
+}
+
+// Rows of this table only, not of tables nested in its cells
+fn own_rows(table: &NodeRef) -> Vec<(NodeRef, bool)> {
+    let mut rows = Vec::new();
+    for child in table.children() {
+        match tag(&child).as_deref() {
+            Some("tr") => rows.push((child, false)),
+            Some(section @ ("thead" | "tbody" | "tfoot")) => rows.extend(
+                child
+                    .children()
+                    .filter(|row| tag(row).as_deref() == Some("tr"))
+                    .map(|row| (row, section == "thead")),
+            ),
+            _ => {}
+        }
+    }
+    rows
+}
+
+/// Lays the rows out on a grid, copying spanned cells into each slot they cover.
+fn grid(rows: &[(NodeRef, bool)]) -> Vec<Vec<String>> {
+    let mut grid: Vec<Vec<Option<String>>> = Vec::new();
+    for (y, (row, _)) in rows.iter().enumerate() {
+        if grid.len() <= y {
+            grid.resize(y + 1, Vec::new());
+        }
+        let mut x = 0;
+        for cell in row.children().filter(|cell| matches!(tag(cell).as_deref(), Some("td" | "th"))) {
+            while grid[y].get(x).is_some_and(Option::is_some) {
+                x += 1;
+            }
+            let text = text_of(&cell);
+            let (colspan, rowspan) = (span(&cell, "colspan"), span(&cell, "rowspan").min(rows.len() - y));
+            for dy in 0..rowspan {
+                if grid.len() <= y + dy {
+                    grid.resize(y + dy + 1, Vec::new());
+                }
+                let line = &mut grid[y + dy];
+                if line.len() < x + colspan {
+                    line.resize(x + colspan, None);
+                }
+                for slot in &mut line[x..x + colspan] {
+                    *slot = Some(text.clone());
+                }
+            }
+            x += colspan;
+        }
+    }
+    let width = grid.iter().map(Vec::len).max().unwrap_or(0);
+    grid.into_iter()
+        .map(|line| {
+            let mut line: Vec<String> = line.into_iter().map(Option::unwrap_or_default).collect();
+            line.resize(width, String::new());
+            line
+        })
+        .collect()
+}
+
+/// Header rows (a `<thead>`, or a leading row of `<th>` cells) are merged
+/// into one header per column. Tables are numbered by `extract_tables`.
+fn table_of(table: &NodeRef) -> Option<Table> {
+    let rows = own_rows(table);
+    let mut lines = grid(&rows);
+    if lines.is_empty() || lines[0].is_empty() {
+        return None;
+    }
+    let mut header_rows = rows.iter().take_while(|(_, in_head)| *in_head).count();
+    if header_rows == 0 {
+        let first = &rows[0].0;
+        let all_th = first
+            .children()
+            .filter_map(|cell| tag(&cell))
+            .filter(|name| name == "td" || name == "th")
+            .all(|name| name == "th");
+        header_rows = usize::from(all_th && rows.len() > 1);
+    }
+
+    let body = lines.split_off(header_rows);
+    let headers = (0..body.first().or(lines.first()).map_or(0, Vec::len))
+        .map(|column| {
+            let mut parts: Vec<&str> = Vec::new();
+            for line in &lines {
+                let part = line[column].as_str();
+                // A header spanning several columns or rows appears once
+                if !part.is_empty() && parts.last() != Some(&part) {
+                    parts.push(part);
+                }
+            }
+            parts.join(" / ")
+        })
+        .collect();
+    let caption = table
+        .children()
+        .find(|child| tag(child).as_deref() == Some("caption"))
+        .map(|caption| text_of(&caption))
+        .filter(|caption| !caption.is_empty());
+    Some(Table { index: 0, caption, headers, rows: body })
+}
+
+/// The tables of a response document, outermost first in document order.
+pub fn extract_tables(document: &NodeRef) -> Vec<Table> {
+    let Ok(tables) = document.select("table") else { return Vec::new() };
+    tables
+        .filter_map(|table| table_of(table.as_node()))
+        .enumerate()
+        .map(|(index, table)| Table { index, ..table })
+        .collect()
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) || value.starts_with(' ') || value.ends_with(' ') {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+pub fn to_csv(table: &Table) -> String {
+    std::iter::once(&table.headers)
+        .filter(|headers| headers.iter().any(|header| !header.is_empty()))
+        .chain(&table.rows)
+        .map(|line| line.iter().map(|value| csv_field(value)).collect::<Vec<_>>().join(","))
+        .map(|line| line + "\r\n")
+        .collect()
+}
+
+/// Rows as objects keyed by header; empty and repeated headers get a
+/// column number so no value is lost.
+pub fn to_json(table: &Table) -> Value {
+    let mut keys: Vec<String> = Vec::new();
+    for (column, header) in table.headers.iter().enumerate() {
+        let key = if header.is_empty() || keys.contains(header) {
+            format!("{} {}", if header.is_empty() { "Column" } else { header }, column + 1)
+        } else {
+            header.clone()
+        };
+        keys.push(key);
+    }
+    Value::Array(
+        table
+            .rows
+            .iter()
+            .map(|row| {
+                let object: Map<String, Value> =
+                    keys.iter().cloned().zip(row.iter().map(|value| Value::String(value.clone()))).collect();
+                Value::Object(object)
+            })
+            .collect(),
+    )
+}
+
+/// Returns a step's table as CSV or JSON, and writes it to `path` when given.
+#[command]
+pub async fn export_table(
+    runs: tauri::State<'_, RunRegistry>,
+    run_id: String,
+    step_index: usize,
+    table_index: usize,
+    format: TableFormat,
+    path: Option<String>,
+) -> Result<String, String> {
+    let run = runs.get(&run_id).ok_or_else(|| format!("Run '{}' not found", run_id))?;
+    let table = run
+        .steps
+        .iter()
+        .find(|step| step.index == step_index)
+        .ok_or_else(|| format!("Step {} not found in run '{}'", step_index, run_id))?
+        .tables
+        .get(table_index)
+        .ok_or_else(|| format!("Step {} has no table {}", step_index, table_index))?;
+    let content = match format {
+        TableFormat::Csv => to_csv(table),
+        TableFormat::Json => serde_json::to_string_pretty(&to_json(table)).map_err(|e| e.to_string())?,
+    };
+    if let Some(path) = path {
+        std::fs::write(&path, &content).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+        log_with_context("TABLES", &format!("{}: step {} table {} saved to {}", run_id, step_index, table_index, path));
+    }
+    Ok(content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kuchikiki::traits::TendrilSink;
+
+    fn tables(html: &str) -> Vec<Table> {
+        extract_tables(&kuchikiki::parse_html().one(html))
+    }
+
+    #[test]
+    fn test_merged_cells_fill_every_slot() {
+        let html = r#"<table><caption>Plans</caption>
+            <thead><tr><th rowspan="2">Plan</th><th colspan="2">Price</th></tr><tr><th>Monthly</th><th>Yearly</th></tr></thead>
+            <tbody><tr><td rowspan="2">Pro</td><td>$20</td><td>$200</td></tr><tr><td colspan="2">Contact us</td></tr>
+            <tr><td>Free</td><td>$0</td></tr></tbody></table>"#;
+        let table = &tables(html)[0];
+        assert_eq!(table.caption.as_deref(), Some("Plans"));
+        assert_eq!(table.headers, ["Plan", "Price / Monthly", "Price / Yearly"]);
+        assert_eq!(
+            table.rows,
+            [["Pro", "$20", "$200"], ["Pro", "Contact us", "Contact us"], ["Free", "$0", ""]]
+        );
+    }
+
+    #[test]
+    fn test_headerless_tables_and_nesting() {
+        let html = r#"<table><tr><td>a</td><td><table><tr><th>x</th></tr><tr><td>y</td></tr></table></td></tr></table>"#;
+        let found = tables(html);
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].headers, ["", ""]);
+        assert_eq!(found[0].rows, [["a", "x y"]]);
+        assert_eq!((found[1].index, found[1].headers.clone()), (1, vec!["x".to_string()]));
+    }
+
+    #[test]
+    fn test_csv_and_json_export() {
+        let table = Table {
+            index: 0,
+            caption: None,
+            headers: vec!["Name".to_string(), "".to_string(), "Name".to_string()],
+            rows: vec![vec!["Doe, Jane".to_string(), "said \"hi\"".to_string(), "x".to_string()]],
+        };
+        assert_eq!(to_csv(&table), "Name,,Name\r\n\"Doe, Jane\",\"said \"\"hi\"\"\",x\r\n");
+        assert_eq!(
+            to_json(&table),
+            serde_json::json!([{ "Name": "Doe, Jane", "Column 2": "said \"hi\"", "Name 3": "x" }])
+        );
+    }
+}
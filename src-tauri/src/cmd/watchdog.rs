@@ -0,0 +1,238 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{command, Manager};
+use crate::cmd::guard;
+use crate::cmd::runs::RunRegistry;
+use crate::cmd::scripts::run_script;
+use crate::cmd::sessions::{WebviewSession, WebviewSessions};
+use crate::cmd::webview::create_webview;
+use crate::utils::{log_with_context, log_error, log_success, log_warning};
+
+pub const WEBVIEW_UNHEALTHY_EVENT: &str = "webview://unhealthy";
+pub const WEBVIEW_RECREATED_EVENT: &str = "webview://recreated";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchdogSettings {
+    pub enabled: bool,
+    pub interval_ms: u64,
+    pub heartbeat_timeout_ms: u64,
+    // Window is recreated after heartbeats have failed for this long
+    pub unhealthy_after_ms: u64,
+    pub max_recreations: u32,
+}
+
+impl Default for WatchdogSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_ms: 5000,
+            heartbeat_timeout_ms: 2000,
+            unhealthy_after_ms: 30000,
+            max_recreations: 3,
+        }
+    }
+}
+
+struct Heartbeat {
+    last_ok: Instant,
+    healthy: bool,
+    recreations: u32,
+}
+
+impl Heartbeat {
+    fn new() -> Self {
+        Self { last_ok: Instant::now(), healthy: true, recreations: 0 }
+    }
+}
+
+#[derive(Default)]
+pub struct Watchdog {
+    settings: Mutex<WatchdogSettings>,
+    heartbeats: Mutex<HashMap<String, Heartbeat>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WebviewHealth {
+    pub label: String,
+    pub healthy: bool,
+    pub ms_since_heartbeat: u64,
+    pub recreations: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WebviewRecreated {
+    pub label: String,
+    pub service_id: Option<String>,
+    pub run_id: Option<String>,
+    pub url: String,
+    pub reason: String,
+    pub recreations: u32,
+}
+
+/// Background loop started from `main`: heartbeats every managed window and
+/// recreates the ones that stopped answering.
+pub async fn run(app: tauri::AppHandle) {
+    log_with_context("WATCHDOG", "Webview watchdog started");
+    loop {
+        let settings = app.state::<Watchdog>().settings.lock().unwrap().clone();
+        tokio::time::sleep(Duration::from_millis(settings.interval_ms)).await;
+        if !settings.enabled {
+            continue;
+        }
+
+        for session in app.state::<WebviewSessions>().all() {
+            check_window(&app, &session, &settings).await;
+        }
+    }
+}
+
+async fn check_window(app: &tauri::AppHandle, session: &WebviewSession, settings: &WatchdogSettings) {
+    let watchdog = app.state::<Watchdog>();
+    let window = match app.get_window(&session.label) {
+        Some(window) => window,
+        None => {
+            watchdog.heartbeats.lock().unwrap().remove(&session.label);
+            return;
+        }
+    };
+
+    // Login and SSO pages have no IPC access, so silence there is not a hang
+    let has_ipc = guard::has_ipc_access(app, &window.url());
+    let answered = has_ipc
+        && run_script(&window, "heartbeat", &json!({}), Duration::from_millis(settings.heartbeat_timeout_ms))
+            .await
+            .is_ok();
+
+    let (silent_for, recreations) = {
+        let mut heartbeats = watchdog.heartbeats.lock().unwrap();
+        let beat = heartbeats.entry(session.label.clone()).or_insert_with(Heartbeat::new);
+        if answered || !has_ipc {
+            if !beat.healthy {
+                log_success("WATCHDOG", &format!("{} is responding again", session.label));
+            }
+            beat.last_ok = Instant::now();
+            beat.healthy = true;
+            return;
+        }
+        (beat.last_ok.elapsed(), beat.recreations)
+    };
+
+    if silent_for < Duration::from_millis(settings.unhealthy_after_ms) {
+        return;
+    }
+
+    let reason = format!("No heartbeat for {} ms", silent_for.as_millis());
+    if let Some(beat) = watchdog.heartbeats.lock().unwrap().get_mut(&session.label) {
+        beat.healthy = false;
+    }
+    let _ = app.emit_all(WEBVIEW_UNHEALTHY_EVENT, health_of(&session.label, &watchdog));
+
+    if recreations >= settings.max_recreations {
+        log_error("WATCHDOG", &format!("{} is hung but was already recreated {} times", session.label, recreations));
+        return;
+    }
+    if let Err(e) = recreate_window(app, session, &reason).await {
+        log_error("WATCHDOG", &format!("Failed to recreate {}: {}", session.label, e));
+    }
+}
+
+fn health_of(label: &str, watchdog: &Watchdog) -> Option<WebviewHealth> {
+    watchdog.heartbeats.lock().unwrap().get(label).map(|beat| WebviewHealth {
+        label: label.to_string(),
+        healthy: beat.healthy,
+        ms_since_heartbeat: beat.last_ok.elapsed().as_millis() as u64,
+        recreations: beat.recreations,
+    })
+}
+
+/// Closes the window and opens a new one with the same label, profile and
+/// window mode at its last URL, then tells the owning run to retry its step.
+pub async fn recreate_window(app: &tauri::AppHandle, session: &WebviewSession, reason: &str) -> Result<(), String> {
+    log_warning("WATCHDOG", &format!("Recreating {}: {}", session.label, reason));
+    let window = app
+        .get_window(&session.label)
+        .ok_or_else(|| "Window not found".to_string())?;
+    let url = window.url().to_string();
+
+    window.close().map_err(|e| format!("Failed to close window: {}", e))?;
+    for _ in 0..50 {
+        if app.get_window(&session.label).is_none() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+
+    let reopen = |url: String| {
+        create_webview(
+            app.clone(),
+            session.label.clone(),
+            url,
+            session.service_id.clone(),
+            Some(session.profile.clone()),
+            session.run_id.clone(),
+            Some(session.window_mode),
+        )
+    };
+    // The last URL may be a login or error page outside the guard; fall back to the original
+    let url = match reopen(url.clone()).await {
+        Ok(_) => url,
+        Err(e) => {
+            log_warning("WATCHDOG", &format!("Reopening at {} failed ({}), using {}", url, e, session.url));
+            reopen(session.url.clone()).await?;
+            session.url.clone()
+        }
+    };
+
+    let watchdog = app.state::<Watchdog>();
+    let recreations = {
+        let mut heartbeats = watchdog.heartbeats.lock().unwrap();
+        let beat = heartbeats.entry(session.label.clone()).or_insert_with(Heartbeat::new);
+        beat.last_ok = Instant::now();
+        beat.healthy = true;
+        beat.recreations += 1;
+        beat.recreations
+    };
+
+    if let Some(run_id) = session.run_id.as_deref() {
+        let note = format!("Window {} recreated ({}); current step should be retried", session.label, reason);
+        let _ = app.state::<RunRegistry>().update(run_id, |run| run.warnings.push(note));
+    }
+    let _ = app.emit_all(
+        WEBVIEW_RECREATED_EVENT,
+        WebviewRecreated {
+            label: session.label.clone(),
+            service_id: session.service_id.clone(),
+            run_id: session.run_id.clone(),
+            url,
+            reason: reason.to_string(),
+            recreations,
+        },
+    );
+    log_success("WATCHDOG", &format!("{} recreated", session.label));
+    Ok(())
+}
+
+#[command]
+pub async fn get_watchdog_status(app: tauri::AppHandle) -> Result<Vec<WebviewHealth>, String> {
+    let watchdog = app.state::<Watchdog>();
+    let labels: Vec<String> = watchdog.heartbeats.lock().unwrap().keys().cloned().collect();
+    Ok(labels.iter().filter_map(|label| health_of(label, &watchdog)).collect())
+}
+
+#[command]
+pub async fn set_watchdog_settings(app: tauri::AppHandle, settings: WatchdogSettings) -> Result<WatchdogSettings, String> {
+    if settings.interval_ms == 0 || settings.heartbeat_timeout_ms == 0 {
+        return Err("Watchdog intervals must be greater than zero".to_string());
+    }
+    log_with_context("WATCHDOG", &format!("New settings: {:?}", settings));
+    *app.state::<Watchdog>().settings.lock().unwrap() = settings.clone();
+    Ok(settings)
+}
+
+#[command]
+pub async fn get_watchdog_settings(app: tauri::AppHandle) -> Result<WatchdogSettings, String> {
+    Ok(app.state::<Watchdog>().settings.lock().unwrap().clone())
+}
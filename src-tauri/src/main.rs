@@ -7,15 +7,13 @@ mod utils;
 
 fn main() {
     tauri::Builder::default()
-        .invoke_handler(tauri::generate_handler![
+        .invoke_handler(cmd::guard::guard_invoke(tauri::generate_handler![
             cmd::chains::run_chain,
             cmd::clipboard::safe_copy,
             cmd::clipboard::safe_paste,
             cmd::meta::log_action,
             cmd::webview::create_webview,
             cmd::webview::inject_script,
-            cmd::webview::wait_for_full_response,
-            cmd::webview::wait_for_response_event,
             cmd::webview::extract_content_from_window,
             cmd::webview::extract_monitored_content,
             cmd::webview::get_full_content_chunks,
@@ -37,10 +35,63 @@ fn main() {
             cmd::config::save_webai_selectors,
             cmd::config::get_config_paths,
             cmd::config::load_model_profiles,
-        ])
+            cmd::profiles::list_profiles,
+            cmd::profiles::create_profile,
+            cmd::profiles::delete_profile,
+            cmd::profiles::set_service_profile,
+            cmd::runs::start_run,
+            cmd::runs::get_run,
+            cmd::runs::finish_run,
+            cmd::runs::record_step,
+            cmd::runs::link_webview_run,
+            cmd::artifacts::save_artifacts,
+            cmd::tables::export_table,
+            cmd::media::download_response_media,
+            cmd::bridge::webview_eval_result,
+            cmd::login::check_login_state,
+            cmd::login::wait_for_login,
+            cmd::background::check_background_health,
+            cmd::background::reveal_webview,
+            cmd::background::conceal_webview,
+            cmd::console::webview_console_report,
+            cmd::console::get_webview_console,
+            cmd::console::set_console_log_level,
+            cmd::watchdog::get_watchdog_status,
+            cmd::watchdog::get_watchdog_settings,
+            cmd::watchdog::set_watchdog_settings,
+            cmd::snapshot::capture_failure_snapshot,
+            cmd::injection::inject_prompt,
+            cmd::scripts::list_scripts,
+            cmd::continuation::continue_response,
+            cmd::service_errors::check_service_error,
+            cmd::models::select_model,
+            cmd::conversation::new_chat,
+            cmd::conversation::get_conversation_url,
+            cmd::conversation::extract_conversation,
+            cmd::extraction::extract_response,
+            cmd::extraction::convert_html_to_markdown,
+            cmd::completion::wait_for_completion,
+        ]))
+        .manage(cmd::runs::RunRegistry::default())
+        .manage(cmd::sessions::WebviewSessions::default())
+        .manage(cmd::bridge::EvalBridge::default())
+        .manage(cmd::guard::IpcGrants::default())
+        .manage(cmd::console::WebviewConsole::default())
+        .manage(cmd::watchdog::Watchdog::default())
+        .on_window_event(|event| {
+            if let tauri::WindowEvent::Destroyed = event.event() {
+                let window = event.window();
+                window.state::<cmd::sessions::WebviewSessions>().remove(window.label());
+                window.state::<cmd::console::WebviewConsole>().clear(window.label());
+            }
+        })
         .setup(|app| {
             println!("Tauri app setup starting...");
             
+            cmd::config::register_bundled_config(app);
+            
+            tauri::async_runtime::spawn(cmd::watchdog::run(app.handle()));
+            
             // Try to get the main window
             match app.get_window("main") {
                 Some(window) => {
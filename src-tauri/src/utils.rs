@@ -67,6 +67,17 @@ fn get_log_directory() -> Result<PathBuf, Box<dyn std::error::Error>> {
     Ok(current_dir.join("logs"))
 }
 
+// Root of what the backend persists besides logs (profiles, runs, incidents),
+// kept out of the log folder so clearing logs cannot take it along
+pub fn get_app_data_directory() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    if let Some(data_dir) = dirs::data_dir() {
+        return Ok(data_dir.join("4AI-Lab").join("data"));
+    }
+
+    let current_dir = std::env::current_dir()?;
+    Ok(current_dir.join("data"))
+}
+
 // Get log file path for external access
 pub fn get_log_file_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
     let log_dir = get_log_directory()?;
@@ -1,4 +1,4 @@
-import type { AiServiceId, ChainTag, ChainTemplate } from './types';
+import type { AiServiceId, ChainStep, ChainTag, ChainTemplate } from './types';
 
 export const chainTemplates: Record<ChainTag, ChainTemplate> = {
   creative: {
@@ -36,3 +36,17 @@ export function buildChain(prompt: string): AiServiceId[] {
   const tag = suggestChainTag(prompt);
   return tag ? chainTemplates[tag].chain : ['chatgpt']; // fallback
 }
+
+// `chatgpt:o1` -> { serviceId: 'chatgpt', model: 'o1' }
+export function parseChainStep(step: ChainStep): { serviceId: AiServiceId; model?: string } {
+  const [serviceId, model] = step.split(':', 2) as [AiServiceId, string | undefined];
+  return model && model.trim() ? { serviceId, model: model.trim() } : { serviceId };
+}
+
+// Adds the configured model to each step: ['chatgpt'] + { chatgpt: 'o1' } -> ['chatgpt:o1']
+export function withModels(chain: AiServiceId[], models: Partial<Record<AiServiceId, string>>): ChainStep[] {
+  return chain.map((serviceId) => {
+    const model = models[serviceId]?.trim();
+    return model ? `${serviceId}:${model}` : serviceId;
+  });
+}
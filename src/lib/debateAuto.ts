@@ -4,30 +4,22 @@ import { cleanResponse } from './smartExtract';
 import { logger } from './logger';
 import type { AiServiceId } from './types';
 
-const AI_SERVICES: Record<AiServiceId, { url: string; selector: string; inputSelector: string; submitSelector: string }> = {
+const AI_SERVICES: Record<AiServiceId, { url: string; selector: string }> = {
   chatgpt: {
     url: 'https://chat.openai.com',
-    selector: '[data-message-author-role="assistant"] .markdown',
-    inputSelector: '#prompt-textarea',
-    submitSelector: '[data-testid="send-button"]'
+    selector: '[data-message-author-role="assistant"] .markdown'
   },
   claude: {
     url: 'https://claude.ai',
-    selector: '.font-claude-message',
-    inputSelector: '.ProseMirror',
-    submitSelector: 'button[aria-label="Send Message"]'
+    selector: '.font-claude-message'
   },
   gemini: {
     url: 'https://gemini.google.com',
-    selector: '[data-response-id] .markdown',
-    inputSelector: '.ql-editor',
-    submitSelector: 'button[aria-label="Send message"]'
+    selector: '[data-response-id] .markdown'
   },
   copilot: {
     url: 'https://copilot.microsoft.com',
-    selector: 'cib-message-group[source="bot"] .ac-textBlock',
-    inputSelector: '#searchbox',
-    submitSelector: '#search_icon'
+    selector: 'cib-message-group[source="bot"] .ac-textBlock'
   }
 };
 
@@ -54,57 +46,15 @@ export async function runAutoDebate(
     try {
       // 1. Utwórz WebView
       const label = `auto-${serviceId}-${Date.now()}`;
-      await invoke('create_webview', { label, url: service.url });
+      await invoke('create_webview', { label, url: service.url, serviceId });
       
       // 2. Poczekaj na załadowanie i wstrzyknij prompt
       await new Promise(resolve => setTimeout(resolve, 3000)); // Poczekaj na załadowanie
       
       const promptToSend = buildChainPrompt(context, serviceId, userPrompt, maxWords);
       
-      const injectionScript = `
-        (function() {
-          const input = document.querySelector('${service.inputSelector}');
-          const submit = document.querySelector('${service.submitSelector}');
-          
-          if (input && submit) {
-            input.focus();
-            input.value = '';
-            input.textContent = '';
-            
-            // Bezpieczne escapowanie promptu
-            const promptText = ${JSON.stringify(promptToSend)};
-            
-            // Różne metody wstawiania tekstu
-            if (input.value !== undefined) {
-              input.value = promptText;
-            }
-            if (input.textContent !== undefined) {
-              input.textContent = promptText;
-            }
-            if (input.innerHTML !== undefined) {
-              input.innerHTML = promptText;
-            }
-            
-            // Symuluj wydarzenia
-            input.dispatchEvent(new Event('input', { bubbles: true }));
-            input.dispatchEvent(new Event('change', { bubbles: true }));
-            
-            // Poczekaj i kliknij submit
-            setTimeout(() => {
-              submit.click();
-              // Backup: Enter key
-              setTimeout(() => {
-                input.dispatchEvent(new KeyboardEvent('keydown', { key: 'Enter', bubbles: true }));
-              }, 500);
-            }, 1000);
-            
-            return 'Prompt injected and submitted';
-          }
-          return 'Error: Input or submit button not found';
-        })();
-      `;
-      
-      await invoke('inject_script', { label, script: injectionScript });
+      // Skrypt wstrzykujący generowany w Rust z webai-selectors.json
+      await invoke('inject_prompt', { label, prompt: promptToSend, serviceId });
       
       // 3. Poczekaj na odpowiedź (proste oczekiwanie)
       await new Promise(resolve => setTimeout(resolve, 15000)); // 15 sekund na odpowiedź
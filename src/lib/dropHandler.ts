@@ -1,38 +1,47 @@
-import { invoke } from '@tauri-apps/api/tauri';
+import * as ipc from './ipc';
+import type { DroppedFile } from './ipc';
+
+// Pliki czekające na dołączenie do następnego promptu
+let pending: DroppedFile[] = [];
+
+export const getPendingAttachments = (): DroppedFile[] => [...pending];
+
+export function clearPendingAttachments() {
+  pending = [];
+}
+
+async function attach(paths: string[]) {
+  if (paths.length === 0) return;
+  const list = await ipc.processDropped(paths);
+  console.log('[Drop] odpowiedź Rust:', list);
+  const known = new Set(pending.map(f => f.path));
+  pending.push(...list.filter(f => !known.has(f.path)));
+}
 
 export function initDropZone() {
   const dropZone = document.body;
   console.log('[Drop] listener podpięty');
 
+  // Tauri przechwytuje upuszczone pliki natywnie (fileDropEnabled) i podaje
+  // ich ścieżki w `tauri://file-drop`; DOM-owy File nie ma ścieżki
+  ipc.waitForTauriEnvironment().then(async (isTauri) => {
+    if (!isTauri) return;
+    const { appWindow } = await import('@tauri-apps/api/window');
+    await appWindow.onFileDropEvent((event) => {
+      if (event.payload.type !== 'drop') return;
+      console.log('[Drop] drop-event', event.payload.paths);
+      attach(event.payload.paths).catch(error => console.warn('[Drop] Nie udało się dołączyć plików:', error));
+    });
+  });
+
+  // Bez tego przeglądarka otworzyłaby upuszczony plik zamiast aplikacji
   dropZone.addEventListener('dragover', (e) => {
     e.preventDefault();
     e.stopPropagation();
-    console.log('[Drop] drag-over');
   }, { capture: true });
-
-  dropZone.addEventListener('drop', async (e) => {
+  dropZone.addEventListener('drop', (e) => {
     e.preventDefault();
     e.stopPropagation();
-    console.log('[Drop] drop-event');
-    if (!e.dataTransfer) return;
-
-    // Dziennik: co faktycznie trafia do eventu
-    console.log('[Drop] dataTransfer.items', [...e.dataTransfer.items]);
-    console.log('[Drop] dataTransfer.files', [...e.dataTransfer.files]);
-
-    // Akceptujemy tylko pliki (nie foldery)
-    const files = [...e.dataTransfer.files] as File[];
-    const paths = files
-      .map(f => (f as File & { path?: string }).path)
-      .filter(Boolean);
-
-    if (paths.length === 0) {
-      console.warn('[Drop] Brak ścieżek – przeciągnij konkretne pliki .ts/.tsx');
-      return;
-    }
-
-    const list = await invoke<string[]>('process_dropped', { paths });
-    console.log('[Drop] odpowiedź Rust:', list);
   }, { capture: true });
 
   // Obsługa potencjalnych overlayów Reactowych
@@ -1,3 +1,5 @@
+import type { ConversationTurn, Provenance, Transcript } from './ipc';
+
 const STORAGE_KEY = '4ai-history';
 const MAX_ITEMS = 50;
 
@@ -6,18 +8,41 @@ export interface HistoryItem {
   prompt: string;
   chain: string[];
   timestamp: string;
+  // Set on conversations imported from a service window
+  kind?: 'session';
+  turns?: ConversationTurn[];
+  conversationUrl?: string | null;
+  provenance?: Provenance;
 }
 
 export const history = {
   add(prompt: string, chain: string[]): void {
-    const items: HistoryItem[] = this.getAll();
     const newItem: HistoryItem = {
       id: crypto.randomUUID(),
       prompt: prompt.trim(),
       chain,
       timestamp: new Date().toISOString(),
     };
-    items.unshift(newItem);
+    this.save(newItem);
+  },
+
+  addSession(transcript: Transcript): void {
+    const firstPrompt = transcript.turns.find((t) => t.role === 'user');
+    this.save({
+      id: crypto.randomUUID(),
+      prompt: (firstPrompt?.markdown ?? '').trim(),
+      chain: [transcript.service_id],
+      timestamp: new Date().toISOString(),
+      kind: 'session',
+      turns: transcript.turns,
+      conversationUrl: transcript.conversation_url,
+      provenance: transcript.provenance,
+    });
+  },
+
+  save(item: HistoryItem): void {
+    const items: HistoryItem[] = this.getAll();
+    items.unshift(item);
     if (items.length > MAX_ITEMS) items.pop();
     localStorage.setItem(STORAGE_KEY, JSON.stringify(items));
   },
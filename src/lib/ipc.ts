@@ -1,4 +1,4 @@
-import type { AiServiceId } from './types';
+import type { AiServiceId, ChainStep, InjectionMode, InputStrategy } from './types';
 
 // Check if we're running in Tauri environment - with polling mechanism per Manus recommendation
 async function waitForTauriApi(timeout = 5000): Promise<boolean> {
@@ -83,10 +83,6 @@ const webFallback = {
     console.warn('Web mode: waitForSelector not available - requires desktop app');
     throw new Error('Selector waiting requires desktop app environment');
   },
-  waitForFullResponse: async (_label: string, _selector: string, _stopWords: string[], _timeoutMs: number): Promise<string> => {
-    console.warn('Web mode: waitForFullResponse not available - requires desktop app');
-    throw new Error('Response waiting requires desktop app environment');
-  },
   getTextContent: async (_label: string, _selector: string): Promise<string> => {
     console.warn('Web mode: getTextContent not available - requires desktop app');
     throw new Error('Text extraction requires desktop app environment');
@@ -134,10 +130,19 @@ export const testSelector = async (serviceId: AiServiceId): Promise<boolean> =>
 };
 
 // WebView automation - CRITICAL: These need desktop environment
-export const createWebview = async (label: string, url: string): Promise<string> => {
+export type WindowMode = 'visible' | 'hidden' | 'minimized' | 'offscreen';
+
+export interface WebviewOptions {
+  serviceId?: AiServiceId;
+  profile?: string;
+  runId?: string;
+  windowMode?: WindowMode;
+}
+
+export const createWebview = async (label: string, url: string, options: WebviewOptions = {}): Promise<string> => {
   const isTauri = await waitForTauriApi();
   console.log('[DEBUG] createWebview - Tauri detected:', isTauri);
-  return isTauri && invoke ? invoke('create_webview', { label, url }) : webFallback.createWebview(label, url);
+  return isTauri && invoke ? invoke('create_webview', { label, url, ...options }) : webFallback.createWebview(label, url);
 };
 
 export const injectScript = async (label: string, script: string): Promise<boolean> => {
@@ -150,11 +155,6 @@ export const waitForSelector = async (label: string, selector: string, timeoutMs
   return isTauri && invoke ? invoke('wait_for_selector', { label, selector, timeout_ms: timeoutMs }) : webFallback.waitForSelector(label, selector, timeoutMs);
 };
 
-export const waitForFullResponse = async (label: string, selector: string, stopWords: string[], timeoutMs: number): Promise<string> => {
-  const isTauri = await waitForTauriApi();
-  return isTauri && invoke ? invoke('wait_for_full_response', { label, selector, stopWords, timeoutMs }) : webFallback.waitForFullResponse(label, selector, stopWords, timeoutMs);
-};
-
 export const getTextContent = async (label: string, selector: string): Promise<string> => {
   const isTauri = await waitForTauriApi();
   return isTauri && invoke ? invoke('get_text_content', { label, selector }) : webFallback.getTextContent(label, selector);
@@ -165,16 +165,6 @@ export const closeWebview = async (label: string): Promise<boolean> => {
   return isTauri && invoke ? invoke('close_webview', { label }) : webFallback.closeWebview(label);
 };
 
-export const waitForResponseEvent = async (label: string, timeoutMs: number): Promise<string> => {
-  const isTauri = await waitForTauriApi();
-  if (isTauri && invoke) {
-    return invoke('wait_for_response_event', { label, timeoutMs });
-  } else {
-    console.warn('Web mode: waitForResponseEvent not available - requires desktop app');
-    return Promise.resolve('Web mode not supported');
-  }
-};
-
 export const extractContentFromWindow = async (label: string, serviceId: string): Promise<string> => {
   const isTauri = await waitForTauriApi();
   if (isTauri && invoke) {
@@ -195,4 +185,537 @@ export const extractMonitoredContent = async (label: string): Promise<string> =>
   }
 };
 
+// Browser profiles and runs - desktop only
+export interface BrowserProfile {
+  name: string;
+  description: string | null;
+  user_agent: string | null;
+  created_at: string;
+}
+
+export interface ProfileIndex {
+  profiles: BrowserProfile[];
+  service_defaults: Record<string, string>;
+}
+
+export interface RunMetadata {
+  run_id: string;
+  chain: string[];
+  status: string;
+  started_at: string;
+  finished_at: string | null;
+  profiles: Record<string, string>;
+  window_mode: WindowMode;
+  service_window_modes: Record<string, WindowMode>;
+  warnings: string[];
+  incidents: string[];
+  // service id -> files attached to that service's prompt
+  attachments: Record<string, AttachmentRecord[]>;
+  // service id -> model shown as active when its prompt was sent
+  models: Record<string, string>;
+  steps: StepResult[];
+}
+
+// One chain step's answer as stored with the run
+export interface StepResult {
+  index: number;
+  service_id: string;
+  label: string;
+  model: string | null;
+  answer: string;
+  // Permalink of the thread the answer is in
+  conversation_url: string | null;
+  fresh_context: boolean;
+  // Filled in by the backend from the answer's code blocks
+  artifacts?: CodeArtifact[];
+  // Linked sources, numbered like the answer's footnotes
+  sources?: Source[];
+  // Tables of the answer, merged cells repeated in every slot they cover
+  tables?: Table[];
+  // Images and files of the answer, saved by downloadResponseMedia
+  media?: MediaItem[];
+  // How the answer was obtained; the backend fills it in when missing
+  provenance?: Provenance | null;
+  started_at: string;
+  finished_at: string;
+}
+
+export interface Provenance {
+  service_id: string;
+  // Model shown as active when the prompt was sent
+  model: string | null;
+  label: string;
+  profile: string | null;
+  page_url: string | null;
+  // Response selector that matched
+  selector: string | null;
+  extraction_method: string;
+  completion_reason: string | null;
+  started_at: string;
+  finished_at: string;
+  // Share of the answer quality checks passed, 0 to 1
+  quality_score: number;
+}
+
+export interface Source {
+  number: number;
+  url: string;
+  title: string;
+  // Sentence of the first reference
+  sentence: string;
+}
+
+export interface Table {
+  // Position among the answer's tables
+  index: number;
+  caption: string | null;
+  headers: string[];
+  rows: string[][];
+}
+
+export type TableFormat = 'csv' | 'json';
+
+export interface MediaItem {
+  kind: 'image' | 'file';
+  // Where the page had it (https:, blob: or data: URL)
+  url: string;
+  // Saved file, null when it could not be fetched
+  path: string | null;
+  content_type: string;
+  bytes: number;
+  alt: string | null;
+  error: string | null;
+}
+
+export interface CodeArtifact {
+  // "<step>-<n>", unique within the run
+  id: string;
+  language: string | null;
+  filename: string | null;
+  content: string;
+}
+
+export interface ArtifactWrite {
+  id: string;
+  path: string;
+  exists: boolean;
+  action: 'create' | 'overwrite' | 'skip' | 'unchanged' | 'superseded';
+  bytes: number;
+  // Set when the answer's file name was unsafe and the default name was used
+  warning: string | null;
+}
+
+export interface SaveArtifactsOptions {
+  // Artifact ids to save; all artifacts of the run when omitted
+  ids?: string[];
+  overwrite?: boolean;
+  // Only report what would be written
+  preview?: boolean;
+}
+
+export interface RunOptions {
+  profiles?: Partial<Record<AiServiceId, string>>;
+  windowMode?: WindowMode;
+  serviceWindowModes?: Partial<Record<AiServiceId, WindowMode>>;
+}
+
+async function desktopOnly<T>(command: string, args?: Record<string, unknown>): Promise<T> {
+  const isTauri = await waitForTauriApi();
+  if (isTauri && invoke) {
+    return invoke(command, args);
+  }
+  throw new Error(`${command} requires desktop app environment`);
+}
+
+export const listProfiles = (): Promise<ProfileIndex> => desktopOnly('list_profiles');
+
+export const createProfile = (name: string, description?: string, userAgent?: string): Promise<BrowserProfile> =>
+  desktopOnly('create_profile', { name, description, userAgent });
+
+export const deleteProfile = (name: string): Promise<string> => desktopOnly('delete_profile', { name });
+
+export const setServiceProfile = (serviceId: AiServiceId, profile: string | null): Promise<string> =>
+  desktopOnly('set_service_profile', { serviceId, profile });
+
+export const startRun = (chain: ChainStep[], options: RunOptions = {}): Promise<RunMetadata> =>
+  desktopOnly('start_run', { chain, ...options });
+
+export const getRun = (runId: string): Promise<RunMetadata> => desktopOnly('get_run', { runId });
+
+export const finishRun = (runId: string, status: string): Promise<RunMetadata> =>
+  desktopOnly('finish_run', { runId, status });
+
+// Replaces an earlier result with the same index (retried step)
+export const recordStep = (runId: string, step: StepResult): Promise<RunMetadata> =>
+  desktopOnly('record_step', { runId, step });
+
+export const saveArtifacts = (
+  runId: string,
+  directory: string,
+  options: SaveArtifactsOptions = {}
+): Promise<ArtifactWrite[]> => desktopOnly('save_artifacts', { runId, directory, ...options });
+
+// Saves the step's images and files into the run's artifact folder and lists them on the step
+export const downloadResponseMedia = (runId: string, stepIndex: number): Promise<MediaItem[]> =>
+  desktopOnly('download_response_media', { runId, stepIndex });
+
+// Returns the table as CSV or JSON text, also written to `path` when given
+export const exportTable = (
+  runId: string,
+  stepIndex: number,
+  tableIndex: number,
+  format: TableFormat,
+  path?: string
+): Promise<string> => desktopOnly('export_table', { runId, stepIndex, tableIndex, format, path });
+
+// Pooled windows are created before the run; this ties them to it
+export const linkWebviewRun = (label: string, runId: string, serviceId?: AiServiceId): Promise<void> =>
+  desktopOnly('link_webview_run', { label, runId, serviceId });
+
+// Login state - the backend also emits LOGIN_REQUIRED_EVENT and brings the window forward
+export const LOGIN_REQUIRED_EVENT = 'service://login-required';
+export const LOGIN_RESTORED_EVENT = 'service://login-restored';
+
+export type LoginState = 'logged_in' | 'logged_out' | 'challenge' | 'unknown';
+
+export interface LoginStatus {
+  label: string;
+  service_id: string | null;
+  state: LoginState;
+  url: string;
+  matched: string | null;
+}
+
+export const checkLoginState = (label: string): Promise<LoginStatus> => desktopOnly('check_login_state', { label });
+
+export const waitForLogin = (label: string, timeoutMs?: number): Promise<LoginStatus> =>
+  desktopOnly('wait_for_login', { label, timeoutMs });
+
+// Background windows - revealed automatically when login or a captcha needs the user
+export const BACKGROUND_THROTTLED_EVENT = 'service://background-throttled';
+
+export interface BackgroundHealth {
+  label: string;
+  service_id: string | null;
+  window_mode: WindowMode;
+  visibility_state: string;
+  timer_drift_ms: number;
+  animation_frames: number;
+  throttled: boolean;
+}
+
+export const checkBackgroundHealth = (label: string): Promise<BackgroundHealth> =>
+  desktopOnly('check_background_health', { label });
+
+export const revealWebview = (label: string): Promise<string> => desktopOnly('reveal_webview', { label });
+
+export const concealWebview = (label: string): Promise<string> => desktopOnly('conceal_webview', { label });
+
+// Emitted when a service window tries to leave its allowed domains
+export const NAVIGATION_BLOCKED_EVENT = 'security://navigation-blocked';
+
+export interface NavigationBlocked {
+  label: string;
+  service_id: string | null;
+  url: string;
+  action: 'block' | 'close';
+}
+
+// Console output forwarded from service windows
+export type ConsoleLevel = 'debug' | 'log' | 'info' | 'warn' | 'error';
+
+export interface ConsoleEntry {
+  label: string;
+  service_id: string | null;
+  level: ConsoleLevel;
+  message: string;
+  source: string | null;
+  timestamp: string;
+}
+
+export const getWebviewConsole = (label: string, minLevel?: ConsoleLevel, limit?: number): Promise<ConsoleEntry[]> =>
+  desktopOnly('get_webview_console', { label, minLevel, limit });
+
+export const setConsoleLogLevel = (level: ConsoleLevel): Promise<string> =>
+  desktopOnly('set_console_log_level', { level });
+
+// Hang watchdog - a recreated window means the owning run should retry its current step
+export const WEBVIEW_UNHEALTHY_EVENT = 'webview://unhealthy';
+export const WEBVIEW_RECREATED_EVENT = 'webview://recreated';
+
+export interface WatchdogSettings {
+  enabled: boolean;
+  interval_ms: number;
+  heartbeat_timeout_ms: number;
+  unhealthy_after_ms: number;
+  max_recreations: number;
+}
+
+export interface WebviewHealth {
+  label: string;
+  healthy: boolean;
+  ms_since_heartbeat: number;
+  recreations: number;
+}
+
+export interface WebviewRecreated {
+  label: string;
+  service_id: string | null;
+  run_id: string | null;
+  url: string;
+  reason: string;
+  recreations: number;
+}
+
+// Resolves to an unsubscribe function; outside the desktop app nothing is ever recreated
+export const onWebviewRecreated = async (handler: (event: WebviewRecreated) => void): Promise<() => void> => {
+  if (!(await waitForTauriApi())) {
+    return () => undefined;
+  }
+  const { listen } = await import('@tauri-apps/api/event');
+  return listen<WebviewRecreated>(WEBVIEW_RECREATED_EVENT, (event) => handler(event.payload));
+};
+
+export const getWatchdogStatus = (): Promise<WebviewHealth[]> => desktopOnly('get_watchdog_status');
+
+export const getWatchdogSettings = (): Promise<WatchdogSettings> => desktopOnly('get_watchdog_settings');
+
+export const setWatchdogSettings = (settings: WatchdogSettings): Promise<WatchdogSettings> =>
+  desktopOnly('set_watchdog_settings', { settings });
+
+// Failure snapshots - saved under <app data>/incidents/<incident_id>/
+export interface SelectorMatch {
+  group: string;
+  selector: string;
+  count: number;
+}
+
+export interface IncidentReport {
+  incident_id: string;
+  directory: string;
+  label: string;
+  service_id: string | null;
+  run_id: string | null;
+  reason: string;
+  captured_at: string;
+  url: string;
+  title: string;
+  selectors: SelectorMatch[];
+  html_file: string | null;
+  html_truncated: boolean;
+  dom_error: string | null;
+  screenshot_file: string | null;
+  screenshot_error: string | null;
+  console: ConsoleEntry[];
+}
+
+export const captureFailureSnapshot = (
+  label: string,
+  reason: string,
+  serviceId?: string,
+  runId?: string
+): Promise<IncidentReport> => desktopOnly('capture_failure_snapshot', { label, reason, serviceId, runId });
+
+// Prompt injection generated from webai-selectors.json (see InputStrategy in types.ts)
+// Files dropped on the app, attachable to the next prompt
+export interface DroppedFile {
+  path: string;
+  name: string;
+  extension: string;
+  size: number;
+  mime_type: string;
+}
+
+export interface AttachmentRecord {
+  name: string;
+  size: number;
+  mime_type: string;
+  method: 'file_input' | 'drop' | 'pasted_text';
+  selector: string | null;
+}
+
+export const processDropped = (paths: string[]): Promise<DroppedFile[]> =>
+  desktopOnly('process_dropped', { paths });
+
+export interface InjectionOptions {
+  // Overrides the service's configured injectionMode
+  mode?: InjectionMode;
+  // Fail instead of sending when the editor content differs from the prompt
+  strict?: boolean;
+  // Switch the service to this model first (from a `service:model` chain step)
+  model?: string;
+  // Open a new conversation first so earlier turns do not leak into this one
+  freshContext?: boolean;
+}
+
+export interface NewChat {
+  method: 'button' | 'url';
+  target: string;
+  previous_url: string;
+}
+
+export const newChat = (label: string, serviceId?: AiServiceId): Promise<NewChat> =>
+  desktopOnly('new_chat', { label, serviceId });
+
+// Null until the conversation has a permalink
+export const getConversationUrl = (label: string, serviceId?: AiServiceId): Promise<string | null> =>
+  desktopOnly('get_conversation_url', { label, serviceId });
+
+export interface ConversationTurn {
+  role: 'user' | 'assistant';
+  markdown: string;
+  index: number;
+}
+
+export interface Transcript {
+  label: string;
+  service_id: string;
+  conversation_url: string | null;
+  turns: ConversationTurn[];
+  provenance: Provenance;
+}
+
+// Reads a conversation held by hand in a service window
+export const extractConversation = (label: string, serviceId?: AiServiceId): Promise<Transcript> =>
+  desktopOnly('extract_conversation', { label, serviceId });
+
+export interface ModelSelection {
+  requested: string;
+  // Text of the active-model element after switching
+  active: string;
+  switched: boolean;
+  verified: boolean;
+}
+
+export const selectModel = (label: string, model: string, serviceId?: AiServiceId): Promise<ModelSelection> =>
+  desktopOnly('select_model', { label, model, serviceId });
+
+export interface InjectionReport {
+  label: string;
+  service_id: string;
+  prompt_length: number;
+  mode: InjectionMode;
+  strategy: InputStrategy;
+  input_selector: string;
+  send_selector: string | null;
+  sent_with: 'click' | 'enter';
+  verified: boolean;
+  attachments: AttachmentRecord[];
+  model: ModelSelection | null;
+  new_chat: NewChat | null;
+}
+
+// Attachment paths are checked against the service's limits before anything is typed
+export const injectPrompt = (
+  label: string,
+  prompt: string,
+  serviceId?: AiServiceId,
+  attachments: string[] = [],
+  options: InjectionOptions = {}
+): Promise<InjectionReport> =>
+  desktopOnly('inject_prompt', { label, prompt, serviceId, attachments, options });
+
+// Limit / capacity / error pages recognized from the service's errorSignatures
+export const SERVICE_ERROR_EVENT = 'service://error';
+
+export type ServiceFailure =
+  | { kind: 'rate_limited'; retry_after: number | null }
+  | { kind: 'capacity' }
+  | { kind: 'network_error' }
+  | { kind: 'content_policy_refusal' };
+
+export interface ServiceErrorReport {
+  label: string;
+  service_id: string;
+  failure: ServiceFailure;
+  matched: string;
+  message: string;
+}
+
+// Thrown instead of returning an error page as the answer, so callers can retry or reroute
+export class ServiceFailureError extends Error {
+  constructor(public readonly report: ServiceErrorReport) {
+    super(`${report.service_id}: ${report.failure.kind} (${report.message})`);
+    this.name = 'ServiceFailureError';
+  }
+
+  get failure(): ServiceFailure {
+    return this.report.failure;
+  }
+}
+
+export const checkServiceError = (
+  label: string,
+  serviceId?: AiServiceId,
+  response?: string
+): Promise<ServiceErrorReport | null> => desktopOnly('check_service_error', { label, serviceId, response });
+
+// Long answers: continue buttons / continue prompts until the answer is complete
+export interface ContinuationStep {
+  method: 'button' | 'prompt';
+  trigger: string;
+  fragment_length: number;
+}
+
+export interface LongResponse {
+  label: string;
+  service_id: string;
+  text: string;
+  fragments: string[];
+  continuations: ContinuationStep[];
+  complete: boolean;
+  stop_reason: 'complete' | 'max_continuations' | 'timeout' | 'service_error';
+  failure: ServiceErrorReport | null;
+}
+
+export const continueResponse = (label: string, serviceId?: AiServiceId): Promise<LongResponse> =>
+  desktopOnly('continue_response', { label, serviceId });
+
+// Completion detector: why the backend decided the answer is done
+export type CompletionSignal = 'text_stable' | 'streaming_ended' | 'send_enabled' | 'stop_gone';
+
+export type CompletionReason =
+  | { kind: 'signals'; signals: CompletionSignal[]; score: number }
+  | { kind: 'max_duration' };
+
+export interface CompletionResult {
+  label: string;
+  service_id: string;
+  reason: CompletionReason;
+  text_length: number;
+  elapsed_ms: number;
+}
+
+export const waitForCompletion = (label: string, serviceId?: AiServiceId): Promise<CompletionResult> =>
+  desktopOnly('wait_for_completion', { label, serviceId });
+
+// Latest answer as HTML and as the Markdown passed between chain steps
+export interface ExtractedResponse {
+  label: string;
+  service_id: string;
+  selector: string;
+  html: string;
+  // Citation markers renumbered as [^n] footnotes, defined at the end
+  markdown: string;
+  text: string;
+  sources: Source[];
+  tables: Table[];
+  provenance: Provenance;
+}
+
+export const extractResponse = (label: string, serviceId?: AiServiceId): Promise<ExtractedResponse> =>
+  desktopOnly('extract_response', { label, serviceId });
+
+// With a service, its cleanup rules strip UI chrome first
+export const htmlToMarkdown = (html: string, serviceId?: AiServiceId): Promise<string> =>
+  desktopOnly('convert_html_to_markdown', { html, serviceId });
+
+// Scripts the backend injects into service pages (src-tauri/scripts)
+export interface ScriptInfo {
+  name: string;
+  version: string;
+  description: string;
+  params: string[];
+}
 
+export const listScripts = (): Promise<ScriptInfo[]> => desktopOnly('list_scripts');
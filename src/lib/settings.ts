@@ -1,20 +1,53 @@
+import type { WindowMode } from './ipc';
+import type { AiServiceId } from './types';
+
+// Bumped when a stored value has to be reinterpreted on load
+const VERSION = 2;
+
 type Settings = {
+  version: number;
   autoChain: boolean;
   darkMode: boolean;
+  // Refuse to send unless the input holds exactly the prompt
   strictInjection: boolean;
+  // Type prompts key by key instead of filling the editor in one step
+  humanTyping: boolean;
+  // Start every chain step in a new conversation
+  freshContext: boolean;
+  // Browser profile for every service of a run; empty = each service's default
+  runProfile: string;
+  // How service windows of a run are shown; services may prefer their own background mode
+  windowMode: WindowMode;
+  // Model each service switches to before its step; missing = keep the current one
+  serviceModels: Partial<Record<AiServiceId, string>>;
 };
 
+type BooleanKey = { [K in keyof Settings]: Settings[K] extends boolean ? K : never }[keyof Settings];
+
 const DEFAULT: Settings = {
+  version: VERSION,
   autoChain: true,
   darkMode: false,
-  strictInjection: true,
+  strictInjection: false,
+  humanTyping: false,
+  freshContext: true,
+  runProfile: '',
+  windowMode: 'visible',
+  serviceModels: {},
 };
 
 export const settings = {
   load(): Settings {
     try {
       const raw = localStorage.getItem('4ai-settings');
-      return raw ? { ...DEFAULT, ...JSON.parse(raw) } : DEFAULT;
+      if (!raw) return DEFAULT;
+      const stored = JSON.parse(raw);
+      // Version 1 stored strictInjection: true by default while it did nothing;
+      // it now blocks sending on any mismatch, so it has to be switched on again
+      if ((stored.version ?? 1) < 2) {
+        delete stored.strictInjection;
+      }
+      return { ...DEFAULT, ...stored, version: VERSION };
     } catch {
       return DEFAULT;
     }
@@ -25,7 +58,7 @@ export const settings = {
     localStorage.setItem('4ai-settings', JSON.stringify(updated));
   },
 
-  toggle(key: keyof Settings): void {
+  toggle(key: BooleanKey): void {
     const curr = this.load();
     this.save({ [key]: !curr[key] });
   },
@@ -1,5 +1,8 @@
 export type AiServiceId = 'chatgpt' | 'claude' | 'gemini' | 'copilot';
 
+// A chain entry: a service, optionally with the model to use there (`chatgpt:o1`)
+export type ChainStep = AiServiceId | `${AiServiceId}:${string}`;
+
 // Tauri API declarations
 declare global {
   interface Window {
@@ -19,12 +22,131 @@ export interface AiService {
 }
 
 // New config-based interfaces
+export interface LoginSelectorConfig {
+  loggedInSelectors: string[];
+  loggedOutSelectors: string[];
+  challengeSelectors: string[];
+  loginUrlPatterns: string[];
+}
+
+export interface AttachmentSelectorConfig {
+  fileInputSelectors: string[];
+  dropTargetSelectors: string[];
+  maxFileSizeMb: number;
+  maxFiles: number;
+  // Lowercase extensions without the dot; empty accepts anything
+  allowedExtensions: string[];
+  // Pasted into the prompt as a fenced block instead of uploaded
+  pasteAsTextExtensions: string[];
+  uploadSettleMs: number;
+}
+
+export interface ContinuationConfig {
+  buttonSelectors: string[];
+  // Exact button labels (case-insensitive)
+  buttonTexts: string[];
+  // Present while the answer is still streaming
+  generatingSelectors: string[];
+  // Endings that mean the answer was cut off
+  truncationMarkers: string[];
+  continuePrompt: string;
+  maxContinuations: number;
+  settleMs?: number;
+  fragmentTimeoutMs?: number;
+}
+
+// Each fired signal adds its weight; the answer is done at `threshold`
+export interface CompletionConfig {
+  // Present while the answer streams, e.g. [data-is-streaming='true']
+  streamingSelectors?: string[];
+  stopButtonSelectors?: string[];
+  // Defaults to the service's sendSelectors
+  sendButtonSelectors?: string[];
+  stableMs?: number;
+  minLength?: number;
+  weights?: Partial<Record<'textStable' | 'streamingEnded' | 'sendEnabled' | 'stopGone', number>>;
+  threshold?: number;
+  pollMs?: number;
+  maxDurationMs?: number;
+}
+
+export interface ModelPickerConfig {
+  menuSelectors: string[];
+  optionSelectors: string[];
+  // Element showing the active model's name
+  activeSelectors: string[];
+  // Model id used in chain steps -> labels it appears under in the menu
+  models: Record<string, string[]>;
+}
+
+export interface NewChatConfig {
+  // "New chat" buttons, tried before navigating
+  selectors: string[];
+  // Page that always opens an empty conversation
+  url?: string;
+  // Substrings of the URL once a conversation has a permalink
+  conversationUrlPatterns: string[];
+}
+
+export interface CleanupConfig {
+  // Removed from a copy of the response before its HTML or text is read
+  excludeSelectors: string[];
+  // Regexes matched per line of the extracted text; lines left empty are dropped
+  textFilters: string[];
+}
+
+export interface TurnSelectorConfig {
+  // Each list is tried in order; the first selector with matches is used
+  userSelectors: string[];
+  assistantSelectors: string[];
+}
+
+export interface ErrorSignatureConfig {
+  kind: 'rate_limited' | 'capacity' | 'network_error' | 'content_policy_refusal';
+  // Case-insensitive substrings, only checked in short responses
+  patterns: string[];
+  // Any visible match means the error is shown
+  selectors: string[];
+  maxResponseLength?: number;
+}
+
+export type InputStrategy = 'auto' | 'textarea' | 'react' | 'contenteditable' | 'prosemirror' | 'quill';
+
+export type InjectionMode = 'instant' | 'typing';
+
+export interface TypingConfig {
+  chunkSize: number;
+  chunkDelayMs: number;
+  // Random extra pause per chunk, up to this many ms
+  jitterMs: number;
+  // Key combination that starts a new line without sending
+  newline: 'shift_enter' | 'enter' | 'ctrl_enter';
+}
+
 export interface SelectorConfig {
   inputSelectors: string[];
   sendSelectors: string[];
   responseSelectors: string[];
+  // How the prompt is written into the matched input (default 'auto')
+  inputStrategy?: InputStrategy;
+  injectionMode?: InjectionMode;
+  typing?: TypingConfig;
   version: string;
   fallbackStrategy: 'try_all_selectors' | 'fail_fast';
+  login?: LoginSelectorConfig;
+  attachments?: AttachmentSelectorConfig;
+  continuation?: ContinuationConfig;
+  completion?: CompletionConfig;
+  cleanup?: CleanupConfig;
+  modelPicker?: ModelPickerConfig;
+  newChat?: NewChatConfig;
+  turns?: TurnSelectorConfig;
+  errorSignatures?: ErrorSignatureConfig[];
+  // Used instead of the run's window mode for background runs (some sites throttle hidden tabs)
+  backgroundMode?: 'hidden' | 'minimized' | 'offscreen';
+  // Navigation outside these hosts (and their subdomains) is blocked or closes the window
+  allowedDomains?: string[];
+  offDomainAction?: 'block' | 'close';
 }
 
 // Unified service interface for backward compatibility
@@ -4,15 +4,59 @@ import { test, expect, vi } from 'vitest';
 
 vi.mock('./ipc');
 
-test('runWebViewChain calls waitForFullResponse and getTextContent', async () => {
-  (ipc.createWebview as unknown as ReturnType<typeof vi.fn>).mockResolvedValue('ok');
-  (ipc.closeWebview as unknown as ReturnType<typeof vi.fn>).mockResolvedValue(true);
-  (ipc.waitForFullResponse as unknown as ReturnType<typeof vi.fn>).mockResolvedValue('OK');
-  (ipc.getTextContent as unknown as ReturnType<typeof vi.fn>).mockResolvedValue('AI answer');
+const mocked = (fn: unknown) => fn as ReturnType<typeof vi.fn>;
+
+test('runWebViewChain waits for completion and extracts the response as Markdown', async () => {
+  mocked(ipc.createWebview).mockResolvedValue('ok');
+  mocked(ipc.closeWebview).mockResolvedValue(true);
+  mocked(ipc.injectPrompt).mockResolvedValue({ attachments: [], model: null });
+  mocked(ipc.waitForCompletion).mockResolvedValue({
+    reason: { kind: 'signals', signals: ['text_stable', 'stop_gone'], score: 1 },
+    elapsed_ms: 1200
+  });
+  mocked(ipc.extractResponse).mockResolvedValue({ markdown: '**AI answer**', text: 'AI answer' });
+  mocked(ipc.checkServiceError).mockResolvedValue(null);
+  mocked(ipc.continueResponse).mockResolvedValue({ continuations: [], failure: null });
   const result = await runWebViewChain(['chatgpt'], 'prompt', false);
-  expect(ipc.createWebview).toHaveBeenCalled();
-  expect(ipc.waitForFullResponse).toHaveBeenCalled();
-  expect(ipc.getTextContent).toHaveBeenCalled();
+  expect(ipc.createWebview).toHaveBeenCalledWith(
+    expect.any(String),
+    expect.any(String),
+    expect.objectContaining({ serviceId: 'chatgpt' })
+  );
+  expect(ipc.waitForCompletion).toHaveBeenCalled();
+  expect(ipc.extractResponse).toHaveBeenCalled();
   expect(ipc.closeWebview).toHaveBeenCalled();
-  expect(result).toContain('AI answer');
+  expect(result).toContain('**AI answer**');
+}, 15000);
+
+test('runWebViewChain retries a step whose window the watchdog recreated', async () => {
+  vi.clearAllMocks();
+  let notify: (event: ipc.WebviewRecreated) => void = () => undefined;
+  mocked(ipc.onWebviewRecreated).mockImplementation(async (handler: typeof notify) => {
+    notify = handler;
+    return () => undefined;
+  });
+  mocked(ipc.waitForCompletion)
+    .mockImplementationOnce((label: string) => {
+      setTimeout(() => notify({ label, service_id: 'chatgpt', run_id: null, url: '', reason: 'hung', recreations: 1 }), 0);
+      return new Promise(() => undefined);
+    })
+    .mockResolvedValue({ reason: { kind: 'max_duration' }, elapsed_ms: 10 });
+  const result = await runWebViewChain(['chatgpt'], 'prompt', true);
+  expect(ipc.injectPrompt).toHaveBeenCalledTimes(2);
+  expect(mocked(ipc.injectPrompt).mock.calls[1][0]).toBe(mocked(ipc.injectPrompt).mock.calls[0][0]);
+  expect(result).toContain('**AI answer**');
+}, 25000);
+
+test('runWebViewChain reports a limit banner over an empty response as a service failure', async () => {
+  vi.clearAllMocks();
+  mocked(ipc.waitForCompletion).mockResolvedValue({ reason: { kind: 'max_duration' }, elapsed_ms: 10 });
+  mocked(ipc.extractResponse).mockResolvedValue({ markdown: '', text: '', sources: [], tables: [], provenance: null });
+  mocked(ipc.checkServiceError).mockResolvedValue({
+    service_id: 'chatgpt',
+    failure: { kind: 'rate_limited', retry_after: null },
+    message: 'You have reached your limit'
+  });
+  await expect(runWebViewChain(['chatgpt'], 'prompt', true)).rejects.toBeInstanceOf(ipc.ServiceFailureError);
+  expect(ipc.checkServiceError).toHaveBeenCalledWith(expect.any(String), 'chatgpt', '');
 }, 15000);
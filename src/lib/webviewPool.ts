@@ -13,6 +13,8 @@ interface PooledWebView {
   label: string;
   serviceId: AiServiceId;
   url: string;
+  // Explicit browser profile the window was opened with (none = resolved by the backend)
+  profile?: string;
   createdAt: number;
   lastUsed: number;
   isActive: boolean;
@@ -45,9 +47,15 @@ class WebViewPoolManager {
   /**
    * Get or create a WebView for the specified service
    * Implements Manus's suggestion for persistent connections
+   * A reused window keeps the profile it was opened with, so windows are only
+   * shared between calls asking for the same profile.
    */
-  async getWebView(serviceId: AiServiceId, url: string): Promise<string> {
-    const existingWebView = this.findWebViewForService(serviceId);
+  async getWebView(
+    serviceId: AiServiceId,
+    url: string,
+    options: Omit<ipc.WebviewOptions, 'serviceId'> = {}
+  ): Promise<string> {
+    const existingWebView = this.findWebViewForService(serviceId, options.profile);
     
     if (existingWebView && existingWebView.isInitialized) {
       existingWebView.lastUsed = Date.now();
@@ -72,6 +80,7 @@ class WebViewPoolManager {
       label,
       serviceId,
       url,
+      profile: options.profile,
       createdAt: Date.now(),
       lastUsed: Date.now(),
       isActive: true,
@@ -79,7 +88,7 @@ class WebViewPoolManager {
     };
 
     try {
-      await ipc.createWebview(label, url);
+      await ipc.createWebview(label, url, { ...options, serviceId });
       webView.isInitialized = true;
       this.pool.set(label, webView);
 
@@ -162,9 +171,23 @@ class WebViewPoolManager {
     return stats;
   }
 
-  private findWebViewForService(serviceId: AiServiceId): PooledWebView | null {
+  /**
+   * Most recently used open window of a service, e.g. one the user kept
+   * chatting in after a chain
+   */
+  latestWebViewFor(serviceId: AiServiceId): string | null {
+    let latest: PooledWebView | null = null;
+    for (const webView of this.pool.values()) {
+      if (webView.serviceId === serviceId && (!latest || webView.lastUsed > latest.lastUsed)) {
+        latest = webView;
+      }
+    }
+    return latest?.label ?? null;
+  }
+
+  private findWebViewForService(serviceId: AiServiceId, profile?: string): PooledWebView | null {
     for (const webView of this.pool.values()) {
-      if (webView.serviceId === serviceId && !webView.isActive) {
+      if (webView.serviceId === serviceId && webView.profile === profile && !webView.isActive) {
         return webView;
       }
     }
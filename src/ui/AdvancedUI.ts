@@ -1,12 +1,16 @@
 // ...existing code...
-import { buildChain } from '../lib/chains';
+import { buildChain, withModels } from '../lib/chains';
 import { runWebViewChain } from '../lib/webviewChain';
 import { runDebateChain, debateTemplates } from '../lib/debate';
 import { runQuickDebate, runFullDebate } from '../lib/debateAuto';
 import { history } from '../lib/history';
+import * as ipc from '../lib/ipc';
+import { webViewPool } from '../lib/webviewPool';
 import { settings } from '../lib/settings';
 import { logger } from '../lib/logger';
+import { initDropZone, getPendingAttachments, clearPendingAttachments } from '../lib/dropHandler';
 import type { AiServiceId } from '../lib/types';
+import type { WindowMode } from '../lib/ipc';
 
 export function renderAdvancedUI(rootId: string): void {
   const root = document.getElementById(rootId);
@@ -71,6 +75,7 @@ export function renderAdvancedUI(rootId: string): void {
 
   setupEventHandlers();
   applySettings();
+  initDropZone();
 }
 
 function setupEventHandlers(): void {
@@ -116,7 +121,7 @@ function setupEventHandlers(): void {
         logger.chain(chain, prompt);
         try {
           outputArea.value = 'Starting chain execution...\n';
-          const result = await runWebViewChain(chain, prompt, keepOpen);
+          const result = await runWebViewChain(withModels(chain, settings.load().serviceModels), prompt, keepOpen);
           outputArea.value = result;
           status.textContent = keepOpen ? 'Chain completed - WebViews kept open' : 'Chain completed successfully';
           logger.info('ui', 'Chain finished', { result: result.slice(0, 100) });
@@ -214,8 +219,10 @@ function setupEventHandlers(): void {
     const keepOpenCheckbox = document.getElementById('keepWebViewOpen') as HTMLInputElement;
     const keepOpen = keepOpenCheckbox ? keepOpenCheckbox.checked : true;
     
+    const attachments = getPendingAttachments();
     status.textContent = 'Opening WebViews...';
-    chainInfo.textContent = chain.join(' → ') + (keepOpen ? ' (keeping open)' : ' (auto-close)');
+    chainInfo.textContent = chain.join(' → ') + (keepOpen ? ' (keeping open)' : ' (auto-close)')
+      + (attachments.length ? ` + ${attachments.length} file(s)` : '');
     sendBtn.disabled = true;
     randomBtn.disabled = true;
     
@@ -224,7 +231,16 @@ function setupEventHandlers(): void {
 
     try {
       outputArea.value = 'Starting chain execution...\n';
-      const result = await runWebViewChain(chain, prompt, keepOpen);
+      const { freshContext, runProfile, windowMode, serviceModels } = settings.load();
+      const result = await runWebViewChain(withModels(chain, serviceModels), prompt, keepOpen, {
+        attachments: attachments.map(f => f.path),
+        freshContext,
+        run: {
+          profiles: runProfile ? Object.fromEntries(chain.map(id => [id, runProfile])) : undefined,
+          windowMode
+        }
+      });
+      clearPendingAttachments();
       outputArea.value = result;
       status.textContent = keepOpen ? 'Chain completed - WebViews kept open' : 'Chain completed successfully';
       logger.info('ui', 'Chain finished', { result: result.slice(0, 100) });
@@ -324,12 +340,56 @@ function setupEventHandlers(): void {
 
   // History panel
   historyBtn.addEventListener('click', () => {
-    showPanel('History', renderHistory());
+    showHistory();
   });
 
+  function showHistory(): void {
+    showPanel('History', renderHistory());
+    document.querySelectorAll<HTMLButtonElement>('button[data-import-from]').forEach(btn => {
+      btn.addEventListener('click', () => importConversation(btn.dataset.importFrom as AiServiceId));
+    });
+  }
+
+  // Saves a conversation continued by hand in a service window as a history session
+  async function importConversation(serviceId: AiServiceId): Promise<void> {
+    const label = webViewPool.latestWebViewFor(serviceId);
+    if (!label) {
+      status.textContent = `No open ${serviceId} window to import from`;
+      return;
+    }
+    try {
+      status.textContent = `Importing conversation from ${serviceId}...`;
+      const transcript = await ipc.extractConversation(label, serviceId);
+      history.addSession(transcript);
+      status.textContent = `Imported ${transcript.turns.length} turns from ${serviceId}`;
+      showHistory();
+    } catch (error) {
+      status.textContent = 'Import failed';
+      logger.error('ui', 'Conversation import failed', { serviceId, error: String(error) });
+    }
+  }
+
   // Settings panel
   settingsBtn.addEventListener('click', () => {
     showPanel('Settings', renderSettings());
+    (['autoChain', 'strictInjection', 'humanTyping', 'freshContext'] as const).forEach(key => {
+      const checkbox = document.getElementById(key) as HTMLInputElement | null;
+      checkbox?.addEventListener('change', () => settings.save({ [key]: checkbox.checked }));
+    });
+    // Free-text values are set as properties, not interpolated into the markup
+    const current = settings.load();
+    const runProfile = document.getElementById('runProfile') as HTMLInputElement | null;
+    if (runProfile) runProfile.value = current.runProfile;
+    runProfile?.addEventListener('change', () => settings.save({ runProfile: runProfile.value.trim() }));
+    const windowMode = document.getElementById('windowMode') as HTMLSelectElement | null;
+    windowMode?.addEventListener('change', () => settings.save({ windowMode: windowMode.value as WindowMode }));
+    document.querySelectorAll<HTMLInputElement>('input[data-model-for]').forEach(input => {
+      input.value = current.serviceModels[input.dataset.modelFor as AiServiceId] ?? '';
+      input.addEventListener('change', () => {
+        const serviceId = input.dataset.modelFor as AiServiceId;
+        settings.save({ serviceModels: { ...settings.load().serviceModels, [serviceId]: input.value.trim() || undefined } });
+      });
+    });
   });
 
   // Close panel
@@ -356,11 +416,19 @@ function setupEventHandlers(): void {
 
 function renderHistory(): string {
   const items = history.getAll();
+  const importBar = `
+    <div class="history-import">
+      ${(['chatgpt', 'claude', 'gemini', 'copilot'] as AiServiceId[]).map(serviceId => `
+        <button data-import-from="${serviceId}">⬇ Import ${serviceId}</button>
+      `).join('')}
+    </div>
+  `;
   if (items.length === 0) {
-    return '<p class="empty">No history yet</p>';
+    return `${importBar}<p class="empty">No history yet</p>`;
   }
 
   return `
+    ${importBar}
     <div class="history-search">
       <input type="text" placeholder="Search history..." id="historySearch">
     </div>
@@ -401,6 +469,42 @@ function renderSettings(): string {
           Strict Injection
         </label>
       </div>
+      <div class="setting-item">
+        <label>
+          <input type="checkbox" id="humanTyping" ${current.humanTyping ? 'checked' : ''}>
+          Human-like Typing
+        </label>
+      </div>
+      <div class="setting-item">
+        <label>
+          <input type="checkbox" id="freshContext" ${current.freshContext ? 'checked' : ''}>
+          New Chat per Step
+        </label>
+      </div>
+      <div class="setting-item">
+        <label>
+          Browser Profile
+          <input type="text" id="runProfile" placeholder="service default">
+        </label>
+      </div>
+      <div class="setting-item">
+        <label>
+          Service Windows
+          <select id="windowMode">
+            ${(['visible', 'hidden', 'minimized', 'offscreen'] as const).map(mode => `
+              <option value="${mode}" ${current.windowMode === mode ? 'selected' : ''}>${mode}</option>
+            `).join('')}
+          </select>
+        </label>
+      </div>
+      ${(['chatgpt', 'claude', 'gemini', 'copilot'] as AiServiceId[]).map(serviceId => `
+        <div class="setting-item">
+          <label>
+            Model for ${serviceId}
+            <input type="text" data-model-for="${serviceId}" placeholder="current model">
+          </label>
+        </div>
+      `).join('')}
       <div class="setting-actions">
         <button id="clearHistory">Clear History</button>
         <button id="exportSettings">Export Settings</button>